@@ -5,7 +5,11 @@
 
 pub mod rfs;
 
+pub use rfs::capture;
 pub use rfs::config;
 pub use rfs::config::file_content::FileContent;
+pub use rfs::fs_diff::FsDiff;
 pub use rfs::fs_tester::FsTester;
 pub use rfs::fs_tester_error::{FsTesterError, Result};
+pub use rfs::fs_tester_options::FsTesterOptions;
+pub use rfs::sandbox_naming::SandboxNaming;
@@ -1,6 +1,16 @@
 //! The `rfs` module provides functionality for working with a temporary file system.
-//! It includes configuration, file management, testing, and error handling.
+//! It includes configuration, file management, testing, sandbox comparison, directory capture and error handling.
 
+mod archive;
+pub mod capture;
+mod clone_filter;
 pub mod config;
+mod content_generator;
+pub mod fs_diff;
 pub mod fs_tester;
 pub mod fs_tester_error;
+pub mod fs_tester_options;
+pub mod reaper;
+pub mod sandbox_naming;
+mod sparse;
+mod template;
@@ -0,0 +1,262 @@
+//! The `archive` module extracts the tar, gzip compressed tar and zip archives
+//! of the `archive` configuration entries. Every extracted path is checked like
+//! the configured entries, so the archive can not create anything outside of the sandbox.
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use flate2::read::MultiGzDecoder;
+use tar::EntryType;
+use zip::ZipArchive;
+
+use super::config::file_content::FileContent;
+use super::config::symlink_conf;
+use super::content_generator::open_content;
+use super::fs_tester::FsTester;
+use super::fs_tester_error::{FsTesterError, Result};
+
+const GZIP_MAGIC: &[u8] = b"\x1f\x8b";
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+const EMPTY_ZIP_MAGIC: &[u8] = b"PK\x05\x06";
+const TAR_MAGIC: &[u8] = b"ustar";
+const TAR_MAGIC_OFFSET: usize = 257;
+const TAR_BLOCK_SIZE: usize = 512;
+
+/// The counter of the temporary directories of the process.
+static TEMP_DIR_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Extracts the archive into the existing directory, which is placed `depth` directories
+/// below the sandbox directory. The format is detected by the beginning of the archive.
+pub(crate) fn extract(source: &FileContent, dst_dir: &Path, depth: usize) -> Result<()> {
+    let mut reader = open_content(source)?;
+    let mut header = Vec::with_capacity(TAR_BLOCK_SIZE);
+    reader
+        .by_ref()
+        .take(TAR_BLOCK_SIZE as u64)
+        .read_to_end(&mut header)?;
+
+    let extractor = Extractor { dst_dir, depth };
+    let is_tar =
+        header.get(TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + TAR_MAGIC.len()) == Some(TAR_MAGIC);
+    let is_zip = header.starts_with(ZIP_MAGIC) || header.starts_with(EMPTY_ZIP_MAGIC);
+    let is_gzip = header.starts_with(GZIP_MAGIC);
+    let reader = io::Cursor::new(header).chain(reader);
+
+    if is_tar {
+        extractor.extract_tar(reader)
+    } else if is_gzip {
+        extractor.extract_tar(MultiGzDecoder::new(reader))
+    } else if is_zip {
+        extractor.extract_zip(reader)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the archive should be a tar, tar.gz or zip archive",
+        )
+        .into())
+    }
+}
+
+/// The temporary directory which is removed when it is dropped.
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = FsTester::remove_sandbox(&self.0);
+    }
+}
+
+/// Extracts the archive into a new temporary directory, e.g. to compare the extracted archive
+/// in the sandbox with it. The `depth` is the depth of the archive directory in the sandbox,
+/// so the same symbolic links are accepted.
+pub(crate) fn extract_to_temp_dir(source: &FileContent, depth: usize) -> Result<TempDir> {
+    let dir_path = std::env::temp_dir().join(format!(
+        "rfs_tester_archive_{}_{}",
+        std::process::id(),
+        TEMP_DIR_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::create_dir(&dir_path)?;
+    let temp_dir = TempDir(dir_path);
+    extract(source, temp_dir.path(), depth)?;
+
+    Ok(temp_dir)
+}
+
+struct Extractor<'a> {
+    dst_dir: &'a Path,
+
+    /// The number of directories between the sandbox directory and the destination directory.
+    depth: usize,
+}
+
+impl Extractor<'_> {
+    fn extract_tar(&self, reader: impl Read) -> Result<()> {
+        let mut archive = tar::Archive::new(reader);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.into_owned();
+            let relative_path = self.relative_path(&path)?;
+
+            match entry.header().entry_type() {
+                EntryType::Directory => self.create_dir(&relative_path)?,
+                EntryType::Regular | EntryType::Continuous | EntryType::GNUSparse => {
+                    let mode = entry.header().mode().ok();
+                    self.create_file(&relative_path, &mut entry, mode)?
+                }
+                EntryType::Symlink => {
+                    let target = Self::link_name(&entry)?;
+                    self.create_symlink(&relative_path, &target)?
+                }
+                EntryType::Link => {
+                    let target = self.relative_path(&Self::link_name(&entry)?)?;
+                    self.create_hard_link(&relative_path, &target)?
+                }
+                // The special files and the global headers are skipped.
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn link_name(entry: &tar::Entry<impl Read>) -> Result<PathBuf> {
+        Ok(entry
+            .link_name()?
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "the tar link has no target")
+            })?
+            .into_owned())
+    }
+
+    /// The zip directory is at the end of the archive, so the archive is read into memory.
+    fn extract_zip(&self, mut reader: impl Read) -> Result<()> {
+        let mut data = vec![];
+        reader.read_to_end(&mut data)?;
+        let mut archive = ZipArchive::new(io::Cursor::new(data)).map_err(io::Error::from)?;
+
+        for index in 0..archive.len() {
+            let mut file = archive.by_index(index).map_err(io::Error::from)?;
+            let path = PathBuf::from(file.name());
+            let relative_path = self.relative_path(&path)?;
+
+            if file.is_dir() {
+                self.create_dir(&relative_path)?;
+            } else if file.is_symlink() {
+                let mut target = String::new();
+                file.read_to_string(&mut target)?;
+                self.create_symlink(&relative_path, Path::new(&target))?;
+            } else {
+                let mode = file.unix_mode();
+                self.create_file(&relative_path, &mut file, mode)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the archive path stays inside of the destination directory
+    /// and returns it without the `.` components.
+    fn relative_path(&self, path: &Path) -> Result<PathBuf> {
+        let mut relative_path = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::Normal(name) => relative_path.push(name),
+                Component::CurDir => {}
+                Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                    return Err(FsTesterError::path_escapes_sandbox(path));
+                }
+            }
+        }
+
+        // The extracted symbolic links on the way should not lead the entry outside.
+        let mut ancestor = self.dst_dir.to_path_buf();
+        for component in relative_path
+            .parent()
+            .into_iter()
+            .flat_map(Path::components)
+        {
+            ancestor.push(component);
+            if fs::symlink_metadata(&ancestor).is_ok_and(|metadata| metadata.is_symlink()) {
+                return Err(FsTesterError::path_escapes_sandbox(path));
+            }
+        }
+
+        Ok(relative_path)
+    }
+
+    fn create_dir(&self, relative_path: &Path) -> Result<()> {
+        fs::create_dir_all(self.dst_dir.join(relative_path))?;
+        Ok(())
+    }
+
+    /// Creates the parent directories of the entry and removes the entry extracted before
+    /// with the same path, so the new one is not written through the extracted symbolic link.
+    fn prepare_path(&self, relative_path: &Path) -> Result<PathBuf> {
+        let path = self.dst_dir.join(relative_path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if fs::symlink_metadata(&path).is_ok_and(|metadata| !metadata.is_dir()) {
+            fs::remove_file(&path)?;
+        }
+
+        Ok(path)
+    }
+
+    #[cfg_attr(not(unix), allow(unused_variables))]
+    fn create_file(
+        &self,
+        relative_path: &Path,
+        reader: &mut impl Read,
+        mode: Option<u32>,
+    ) -> Result<()> {
+        let path = self.prepare_path(relative_path)?;
+        let mut file = File::create(&path)?;
+        io::copy(reader, &mut file)?;
+
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(mode & 0o777))?;
+        }
+
+        Ok(())
+    }
+
+    fn create_symlink(&self, relative_path: &Path, target: &Path) -> Result<()> {
+        let link_depth = self.depth + relative_path.components().count().saturating_sub(1);
+        if symlink_conf::target_escapes(target, link_depth) {
+            let link_dir = relative_path.parent().unwrap_or(Path::new(""));
+            return Err(FsTesterError::path_escapes_sandbox(&link_dir.join(target)));
+        }
+
+        let path = self.prepare_path(relative_path)?;
+
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(target, &path)?;
+
+        #[cfg(windows)]
+        if path.parent().unwrap_or(self.dst_dir).join(target).is_dir() {
+            std::os::windows::fs::symlink_dir(target, &path)?;
+        } else {
+            std::os::windows::fs::symlink_file(target, &path)?;
+        }
+
+        Ok(())
+    }
+
+    /// The hard link of the tar archive targets the entry extracted before.
+    fn create_hard_link(&self, relative_path: &Path, target: &Path) -> Result<()> {
+        let path = self.prepare_path(relative_path)?;
+        fs::hard_link(self.dst_dir.join(target), path)?;
+
+        Ok(())
+    }
+}
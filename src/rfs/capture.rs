@@ -0,0 +1,247 @@
+//! The `capture` module turns an existing directory tree back into a configuration,
+//! so a hand-made fixture folder can be stored as a YAML or JSON snapshot.
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use walkdir::WalkDir;
+
+use super::config::file_content::FileContent;
+use super::config::{ConfigEntry, Configuration, DirectoryConf, FileConf, SymlinkConf};
+use super::fs_tester_error::Result;
+
+/// Options of the directory capture.
+#[derive(Debug, Clone, Default)]
+pub struct CaptureOptions {
+    /// Files larger than this size (in bytes) are not inlined into the configuration.
+    /// They are referenced as `original_file` by their path instead.
+    /// If it is `None`, all files are inlined.
+    pub original_file_threshold: Option<u64>,
+}
+
+impl Configuration {
+    /// Walks the directory and produces the configuration which describes it.
+    ///
+    /// The directory becomes the root `directory` entry. Files with UTF-8 content are captured
+    /// as `inline_text`, binary files as `inline_bytes`, and empty files as `empty`.
+    /// Files above the [`CaptureOptions::original_file_threshold`] are captured as `original_file`.
+    /// Symbolic links are captured as `symlink` entries, other special files are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use rfs_tester::config::Configuration;
+    /// # use rfs_tester::capture::CaptureOptions;
+    /// let config = Configuration::capture("src", &CaptureOptions::default()).unwrap();
+    /// let yaml = config.to_yaml().unwrap();
+    ///
+    /// assert!(yaml.contains("lib.rs"));
+    /// ```
+    pub fn capture(dir_path: impl AsRef<Path>, options: &CaptureOptions) -> Result<Configuration> {
+        let dir_path = dir_path.as_ref();
+        if !fs::metadata(dir_path)?.is_dir() {
+            return Err(io::Error::from(io::ErrorKind::NotADirectory).into());
+        }
+
+        let name = match dir_path.file_name() {
+            Some(name) => name.to_string_lossy().into_owned(),
+            None => fs::canonicalize(dir_path)?
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        };
+
+        Ok(Configuration(vec![ConfigEntry::Directory(
+            Self::capture_directory(dir_path, name, 0, options)?,
+        )]))
+    }
+
+    /// Serializes the configuration into the YAML format.
+    pub fn to_yaml(&self) -> Result<String> {
+        Ok(serde_yaml::to_string(self)?)
+    }
+
+    /// Captures the directory placed `depth` directories below the captured root.
+    fn capture_directory(
+        dir_path: &Path,
+        name: String,
+        depth: usize,
+        options: &CaptureOptions,
+    ) -> Result<DirectoryConf> {
+        let mut content = vec![];
+
+        let dir_entries_iter = WalkDir::new(dir_path)
+            .max_depth(1)
+            .sort_by_file_name()
+            .into_iter()
+            .skip(1); // skip self directory
+
+        for entry in dir_entries_iter {
+            let entry = entry?;
+            let entry_name = entry.file_name().to_string_lossy().into_owned();
+            let file_type = entry.file_type();
+
+            if file_type.is_symlink() {
+                let mut conf = SymlinkConf {
+                    name: entry_name,
+                    target: fs::read_link(entry.path())?.to_string_lossy().into_owned(),
+                    ..Default::default()
+                };
+                conf.allow_outside = conf.target_escapes(depth);
+                content.push(ConfigEntry::Symlink(conf));
+            } else if file_type.is_dir() {
+                content.push(ConfigEntry::Directory(Self::capture_directory(
+                    entry.path(),
+                    entry_name,
+                    depth + 1,
+                    options,
+                )?));
+            } else if file_type.is_file() {
+                content.push(ConfigEntry::File(FileConf {
+                    name: entry_name,
+                    content: Self::capture_file_content(entry.path(), options)?,
+                    ..Default::default()
+                }));
+            }
+        }
+
+        Ok(DirectoryConf {
+            name,
+            content,
+            ..Default::default()
+        })
+    }
+
+    fn capture_file_content(file_path: &Path, options: &CaptureOptions) -> Result<FileContent> {
+        let len = fs::metadata(file_path)?.len();
+
+        if len == 0 {
+            return Ok(FileContent::Empty);
+        }
+
+        if options
+            .original_file_threshold
+            .is_some_and(|threshold| len > threshold)
+        {
+            return Ok(FileContent::OriginalFile(
+                file_path.to_string_lossy().into_owned(),
+            ));
+        }
+
+        let data = fs::read(file_path)?;
+        Ok(match String::from_utf8(data) {
+            Ok(text) => FileContent::InlineText(text),
+            Err(err) => FileContent::InlineBytes(err.into_bytes()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::FsTester;
+
+    const YAML_DIR_FOR_CAPTURE: &str = r#"
+    - !directory
+        name: test_capture
+        content:
+          - !file
+              name: text.txt
+              content:
+                !inline_text "test"
+          - !file
+              name: binary.bin
+              content:
+                !inline_bytes [0, 159, 146, 150]
+          - !file
+              name: empty.txt
+              content: !empty
+          - !directory
+              name: inner
+              content:
+                - !symlink
+                    name: text_link
+                    target: ../text.txt
+    "#;
+
+    #[test]
+    fn capture_should_produce_config_matching_the_directory() -> Result<()> {
+        let tester = FsTester::new(YAML_DIR_FOR_CAPTURE, ".")?;
+
+        let config = Configuration::capture(&tester.base_dir, &CaptureOptions::default())?;
+
+        assert!(tester.compare_config(&config)?.is_empty());
+        let ConfigEntry::Directory(root) = &config.0[0] else {
+            panic!("root entry should be a directory");
+        };
+        assert_eq!(
+            root.content
+                .iter()
+                .map(ConfigEntry::name)
+                .collect::<Vec<_>>(),
+            vec!["binary.bin", "empty.txt", "inner", "text.txt"]
+        );
+        assert_eq!(
+            root.content[0],
+            ConfigEntry::File(FileConf {
+                name: String::from("binary.bin"),
+                content: FileContent::InlineBytes(vec![0, 159, 146, 150]),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            root.content[3],
+            ConfigEntry::File(FileConf {
+                name: String::from("text.txt"),
+                content: FileContent::InlineText(String::from("test")),
+                ..Default::default()
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn capture_should_reference_large_files_as_original() -> Result<()> {
+        let tester = FsTester::new(YAML_DIR_FOR_CAPTURE, ".")?;
+        let options = CaptureOptions {
+            original_file_threshold: Some(3),
+        };
+
+        let config = Configuration::capture(&tester.base_dir, &options)?;
+
+        let ConfigEntry::Directory(root) = &config.0[0] else {
+            panic!("root entry should be a directory");
+        };
+        let text_file_path = PathBuf::from(&tester.base_dir).join("text.txt");
+        assert_eq!(
+            root.content[3],
+            ConfigEntry::File(FileConf {
+                name: String::from("text.txt"),
+                content: FileContent::OriginalFile(text_file_path.to_string_lossy().into_owned()),
+                ..Default::default()
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn captured_yaml_snapshot_should_build_the_same_sandbox() -> Result<()> {
+        let tester = FsTester::new(YAML_DIR_FOR_CAPTURE, ".")?;
+
+        let yaml =
+            Configuration::capture(&tester.base_dir, &CaptureOptions::default())?.to_yaml()?;
+        let copy_tester = FsTester::new(&yaml, ".")?;
+
+        assert!(copy_tester.compare(YAML_DIR_FOR_CAPTURE)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn capture_should_return_error_for_missing_directory() {
+        let result = Configuration::capture("unexisting_directory", &CaptureOptions::default());
+
+        assert!(result.is_err_and(|error| error.is_io()));
+    }
+}
@@ -0,0 +1,193 @@
+//! The `clone_filter` module decides which entries of the source directory
+//! are copied by the `clone_directory` configuration entry.
+use std::path::{Path, PathBuf};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use super::config::clone_directory_conf::{CloneDirectoryConf, CloneFidelity};
+use super::fs_tester_error::Result;
+
+const GITIGNORE_FILE_NAME: &str = ".gitignore";
+
+/// The include and exclude patterns of the cloned directory
+/// and the `.gitignore` files found on the way from the source root to the current directory.
+#[derive(Debug, Clone)]
+pub(crate) struct CloneFilter {
+    include: Option<Gitignore>,
+    exclude: Option<Gitignore>,
+    respect_gitignore: bool,
+    exact: bool,
+    skip_special_files: bool,
+
+    /// The source paths of the entries removed from the clone after the copy.
+    removed: Vec<PathBuf>,
+
+    /// The matchers of `.gitignore` files, the deepest directory is the last one.
+    gitignores: Vec<Gitignore>,
+}
+
+impl CloneFilter {
+    /// Builds the filter from the patterns of the cloned directory configuration.
+    /// The patterns are relative to the source directory.
+    pub(crate) fn new(conf: &CloneDirectoryConf) -> Result<Self> {
+        let source = PathBuf::from(&conf.source);
+        Ok(CloneFilter {
+            include: Self::build_matcher(&source, &conf.include)?,
+            exclude: Self::build_matcher(&source, &conf.exclude)?,
+            respect_gitignore: conf.respect_gitignore,
+            exact: conf.fidelity == CloneFidelity::Exact,
+            skip_special_files: conf.skip_special_files,
+            removed: conf.remove.iter().map(|path| source.join(path)).collect(),
+            gitignores: vec![],
+        })
+    }
+
+    /// Returns the filter for the walked directory, which takes its `.gitignore` file into account.
+    pub(crate) fn enter_dir(&self, dir_path: &Path) -> Result<Self> {
+        let gitignore_path = dir_path.join(GITIGNORE_FILE_NAME);
+        if !self.respect_gitignore || !gitignore_path.is_file() {
+            return Ok(self.clone());
+        }
+
+        let mut builder = GitignoreBuilder::new(dir_path);
+        if let Some(err) = builder.add(gitignore_path) {
+            return Err(err.into());
+        }
+
+        let mut filter = self.clone();
+        filter.gitignores.push(builder.build()?);
+        Ok(filter)
+    }
+
+    /// Returns true if the include patterns are set, so only matched entries are copied.
+    pub(crate) fn has_include(&self) -> bool {
+        self.include.is_some()
+    }
+
+    /// Returns true if the directory should not be walked at all.
+    pub(crate) fn skips_dir(&self, dir_path: &Path) -> bool {
+        self.is_excluded(dir_path, true)
+    }
+
+    /// Returns true if the file (or symbolic link) should be copied.
+    pub(crate) fn copies_file(&self, file_path: &Path) -> bool {
+        !self.is_excluded(file_path, false) && self.is_included(file_path, false)
+    }
+
+    /// Returns true if symbolic links are recreated, otherwise they are skipped.
+    pub(crate) fn copies_symlinks(&self) -> bool {
+        self.exact
+    }
+
+    /// Returns true if the special file should be reported as an error, otherwise it is skipped.
+    pub(crate) fn rejects_special_file(&self, file_path: &Path) -> bool {
+        self.exact && !self.skip_special_files && self.copies_file(file_path)
+    }
+
+    /// Returns true if the entry is removed from the clone after the copy.
+    pub(crate) fn is_removed(&self, path: &Path) -> bool {
+        self.removed.iter().any(|removed| removed == path)
+    }
+
+    /// Returns true if the directory itself matches the include patterns,
+    /// so it is kept even without any copied content.
+    pub(crate) fn includes_dir(&self, dir_path: &Path) -> bool {
+        self.is_included(dir_path, true)
+    }
+
+    fn is_included(&self, path: &Path, is_dir: bool) -> bool {
+        match &self.include {
+            Some(include) => include
+                .matched_path_or_any_parents(path, is_dir)
+                .is_ignore(),
+            None => true,
+        }
+    }
+
+    fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        if let Some(exclude) = &self.exclude {
+            if exclude.matched(path, is_dir).is_ignore() {
+                return true;
+            }
+        }
+
+        // The deepest .gitignore file has the priority, like in git.
+        self.gitignores
+            .iter()
+            .rev()
+            .map(|gitignore| gitignore.matched(path, is_dir))
+            .find(|matched| !matched.is_none())
+            .is_some_and(|matched| matched.is_ignore())
+    }
+
+    fn build_matcher(source: &Path, patterns: &[String]) -> Result<Option<Gitignore>> {
+        if patterns.is_empty() {
+            return Ok(None);
+        }
+
+        let mut builder = GitignoreBuilder::new(source);
+        for pattern in patterns {
+            builder.add_line(None, pattern)?;
+        }
+
+        Ok(Some(builder.build()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include: &[&str], exclude: &[&str]) -> CloneFilter {
+        CloneFilter::new(&CloneDirectoryConf {
+            name: String::from("copy"),
+            source: String::from("src"),
+            include: include.iter().map(|pattern| pattern.to_string()).collect(),
+            exclude: exclude.iter().map(|pattern| pattern.to_string()).collect(),
+            ..Default::default()
+        })
+        .expect("patterns should be valid")
+    }
+
+    #[test]
+    fn filter_without_patterns_should_copy_everything() {
+        let filter = filter(&[], &[]);
+
+        assert!(!filter.has_include());
+        assert!(!filter.skips_dir(Path::new("src/rfs")));
+        assert!(filter.copies_file(Path::new("src/rfs/config.rs")));
+    }
+
+    #[test]
+    fn filter_should_apply_include_patterns_to_files_and_their_parents() {
+        let filter = filter(&["*.rs", "!lib.rs", "rfs/config/"], &[]);
+
+        assert!(filter.has_include());
+        assert!(filter.copies_file(Path::new("src/rfs.rs")));
+        assert!(!filter.copies_file(Path::new("src/lib.rs")));
+        assert!(filter.includes_dir(Path::new("src/rfs/config")));
+        assert!(!filter.includes_dir(Path::new("src/rfs")));
+    }
+
+    #[test]
+    fn filter_should_apply_exclude_patterns() {
+        let filter = filter(&[], &["config/", "/lib.rs"]);
+
+        assert!(filter.skips_dir(Path::new("src/rfs/config")));
+        assert!(!filter.skips_dir(Path::new("src/rfs")));
+        assert!(!filter.copies_file(Path::new("src/lib.rs")));
+        assert!(filter.copies_file(Path::new("src/rfs/lib.rs")));
+    }
+
+    #[test]
+    fn filter_should_reject_invalid_pattern() {
+        let result = CloneFilter::new(&CloneDirectoryConf {
+            name: String::from("copy"),
+            source: String::from("src"),
+            exclude: vec![String::from("[z-a")],
+            ..Default::default()
+        });
+
+        assert!(result.is_err_and(|error| error.is_syntax()));
+    }
+}
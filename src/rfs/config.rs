@@ -1,16 +1,30 @@
 //! The 'config` module is responsible for configuring the temporary file system.
-//! Enables settings for directories, files, and links.
+//! Enables settings for directories, files, links, symbolic links and archives.
 
+pub mod archive_conf;
 pub mod clone_directory_conf;
 pub mod config_entry;
 pub mod configuration;
 pub mod directory_conf;
+mod encoding;
 pub mod file_conf;
 pub mod file_content;
 pub mod link_conf;
+pub(crate) mod location;
+mod mode;
+mod size;
+pub mod symlink_conf;
+pub mod timestamp;
+pub mod tree;
+pub mod validation;
 
+pub use archive_conf::ArchiveConf;
 pub use config_entry::ConfigEntry;
 pub use configuration::Configuration;
 pub use directory_conf::DirectoryConf;
 pub use file_conf::FileConf;
 pub use link_conf::LinkConf;
+pub use symlink_conf::SymlinkConf;
+pub use timestamp::Timestamp;
+pub use tree::{DirBuilder, Tree};
+pub use validation::{ConfigIssue, IssueKind};
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+use super::file_content::FileContent;
+
+/// The structure of the archive record in the configuration.
+/// A directory will be created with the given name and the content of the tar,
+/// gzip compressed tar or zip archive extracted into it. The format is detected
+/// by the content of the archive, so the name of the archive file does not matter.
+///
+/// The archive can be taken from any file content, usually from the original file
+/// or the inline base64:
+///
+/// ## yaml:
+///
+/// ```yaml
+/// - !archive
+///     name: project
+///     source: !original_file "fixtures/project.tar.gz"
+/// ```
+///
+/// ```yaml
+/// - !archive
+///     name: small
+///     source: !inline_base64 UEsFBgAAAAAAAAAAAAAAAAAAAAAAAA==
+/// ```
+///
+/// ## json:
+///
+/// ```json
+/// "archive": {
+///   "name": "project",
+///   "source": {
+///     "original_file": "fixtures/project.zip"
+///   }
+/// }
+/// ```
+///
+/// The extracted paths and symbolic link targets are checked like the configured entries,
+/// so the archive entries leading outside of the sandbox, directly or through the extracted
+/// symbolic links, fail the build. The regular files keep their permission bits,
+/// the special files are skipped.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+pub struct ArchiveConf {
+    /// A directory will be created with the given name.
+    pub name: String,
+
+    /// The content of the archive.
+    pub source: FileContent,
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use super::config_entry::ConfigEntry;
+
 /// Structure for directory record in configuration
 /// for example:
 ///
@@ -12,6 +14,44 @@ use serde::{Deserialize, Serialize};
 ///       source: data_dir
 /// ```
 ///
+/// The copied entries can be filtered with gitignore-style patterns:
+///
+/// ```yaml
+/// ---
+///   - !clone_directory
+///       name: test_dir
+///       source: project
+///       include: ["src/", "Cargo.toml"]
+///       exclude: ["*.bak"]
+///       respect_gitignore: true
+/// ```
+///
+/// The `exact` fidelity recreates symbolic links and copies permission bits and timestamps:
+///
+/// ```yaml
+/// ---
+///   - !clone_directory
+///       name: test_dir
+///       source: data_dir
+///       fidelity: exact
+///       skip_special_files: true
+/// ```
+///
+/// The `remove` paths are deleted from the clone and the `content` entries
+/// are created on top of it after the copy completes:
+///
+/// ```yaml
+/// ---
+///   - !clone_directory
+///       name: test_dir
+///       source: template
+///       remove: ["docs/draft.md"]
+///       content:
+///         - !file
+///             name: config.toml
+///             content: !inline_text "debug = true"
+/// ```
+///
 /// ## json:
 ///
 /// ```json
@@ -24,11 +64,106 @@ use serde::{Deserialize, Serialize};
 ///     ]
 /// }
 /// ```
-#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
 pub struct CloneDirectoryConf {
     /// A directory will be created with the given name.
     pub name: String,
 
     /// The name of the destination directory for the copy.
     pub source: String,
+
+    /// Optional permission bits (e.g. `0o555`) applied to the cloned directory
+    /// after all of its content is copied.
+    #[serde(
+        default,
+        deserialize_with = "super::mode::deserialize",
+        serialize_with = "super::mode::serialize",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mode: Option<u32>,
+
+    /// If set, the copied files and directories get the modification and access times
+    /// of their sources instead of the time of copying.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub preserve_timestamps: bool,
+
+    /// Gitignore-style patterns, relative to the source directory, of the entries to copy.
+    /// If it is set, only the matched files, the content of the matched directories and
+    /// the directories on the way to them are copied, other directories are not created.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+
+    /// Gitignore-style patterns, relative to the source directory, of the entries to skip.
+    /// The excluded directories are not walked at all.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+
+    /// If set, the entries ignored by the `.gitignore` files of the source directory are skipped.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub respect_gitignore: bool,
+
+    /// How faithfully the source directory is reproduced.
+    #[serde(default, skip_serializing_if = "CloneFidelity::is_content")]
+    pub fidelity: CloneFidelity,
+
+    /// In the exact fidelity mode, FIFOs, sockets and devices of the source directory
+    /// can not be copied and they are reported as errors. If it is set, they are skipped instead.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub skip_special_files: bool,
+
+    /// In the exact fidelity mode, the cloned symbolic links with the targets which are absolute
+    /// or lead outside of the sandbox are reported as sandbox escapes. If it is set,
+    /// they are recreated like the other symbolic links.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_outside: bool,
+
+    /// If set, the holes of the sparse source files are found with `SEEK_DATA` and `SEEK_HOLE`
+    /// and left as holes in the copies, so the clone takes as little disk space as its source.
+    /// They are supported on Linux, macOS and FreeBSD, on the other platforms the files are
+    /// copied in full.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub preserve_holes: bool,
+
+    /// Paths, relative to the cloned directory, which are deleted after the copy completes.
+    /// The paths should exist in the clone and stay inside of it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove: Vec<String>,
+
+    /// The entries created inside the cloned directory after the copy and the removal.
+    /// Files, links and symbolic links replace the cloned entries with the same name,
+    /// directories are merged with the cloned directories. A cloned directory can be
+    /// replaced by a file only if it is listed in `remove`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub content: Vec<ConfigEntry>,
+}
+
+impl CloneDirectoryConf {
+    /// Returns true if the copied entries get the timestamps of their sources.
+    pub(crate) fn preserves_timestamps(&self) -> bool {
+        self.preserve_timestamps || self.fidelity == CloneFidelity::Exact
+    }
+
+    /// Returns true if the copied entries get the permission bits of their sources.
+    pub(crate) fn preserves_permissions(&self) -> bool {
+        self.fidelity == CloneFidelity::Exact
+    }
+}
+
+/// The fidelity of the cloned directory.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CloneFidelity {
+    /// Only the regular files and directories are copied, symbolic links and special files are skipped.
+    #[default]
+    Content,
+
+    /// Symbolic links are recreated as symbolic links, the permission bits and timestamps
+    /// of the sources are copied, and special files are reported as errors.
+    Exact,
+}
+
+impl CloneFidelity {
+    fn is_content(&self) -> bool {
+        *self == CloneFidelity::Content
+    }
 }
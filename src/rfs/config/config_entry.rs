@@ -1,11 +1,12 @@
 use serde::{Deserialize, Serialize};
 
 use super::{
-    clone_directory_conf::CloneDirectoryConf, directory_conf::DirectoryConf, file_conf::FileConf,
-    link_conf::LinkConf,
+    archive_conf::ArchiveConf, clone_directory_conf::CloneDirectoryConf,
+    directory_conf::DirectoryConf, file_conf::FileConf, link_conf::LinkConf,
+    symlink_conf::SymlinkConf,
 };
 
-/// A configuration item can be a directory, file, or link.
+/// A configuration item can be a directory, file, link, symbolic link or archive.
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
 #[serde(rename_all = "snake_case")]
 pub enum ConfigEntry {
@@ -32,4 +33,91 @@ pub enum ConfigEntry {
     /// and an error will occur if you try to do so.
     /// To enable this feature, you can set the LINKS_ALLOWED environment variable to true.
     Link(LinkConf),
+
+    /// The Symlink directive is used to create a symbolic link.
+    /// The target can be a file or a directory, relative or absolute path,
+    /// or even a non-existent path to get a dangling link.
+    /// Symbolic links do not share the data of their targets,
+    /// so they do not require the LINKS_ALLOWED environment variable.
+    Symlink(SymlinkConf),
+
+    /// The Archive directive is used to create a directory with the name
+    /// specified in the "name" parameter and the content of the tar, tar.gz
+    /// or zip archive from the "source" parameter extracted into it.
+    Archive(ArchiveConf),
+}
+
+impl ConfigEntry {
+    /// The name of the file system entry created by this configuration item.
+    pub fn name(&self) -> &str {
+        match self {
+            ConfigEntry::Directory(conf) => &conf.name,
+            ConfigEntry::CloneDirectory(conf) => &conf.name,
+            ConfigEntry::File(conf) => &conf.name,
+            ConfigEntry::Link(conf) => &conf.name,
+            ConfigEntry::Symlink(conf) => &conf.name,
+            ConfigEntry::Archive(conf) => &conf.name,
+        }
+    }
+
+    /// The kind of the configuration item as it is tagged in the configuration, e.g. `clone_directory`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ConfigEntry::Directory(_) => "directory",
+            ConfigEntry::CloneDirectory(_) => "clone_directory",
+            ConfigEntry::File(_) => "file",
+            ConfigEntry::Link(_) => "link",
+            ConfigEntry::Symlink(_) => "symlink",
+            ConfigEntry::Archive(_) => "archive",
+        }
+    }
+
+    /// The path outside of the sandbox which the item is created from: the source
+    /// of the cloned directory, the original file of the file or the archive,
+    /// or the target of the link.
+    pub fn source(&self) -> Option<&str> {
+        match self {
+            ConfigEntry::CloneDirectory(conf) => Some(&conf.source),
+            ConfigEntry::File(conf) => conf.content.original_file(),
+            ConfigEntry::Archive(conf) => conf.source.original_file(),
+            ConfigEntry::Link(conf) => Some(&conf.target),
+            ConfigEntry::Directory(_) | ConfigEntry::Symlink(_) => None,
+        }
+    }
+}
+
+impl From<DirectoryConf> for ConfigEntry {
+    fn from(conf: DirectoryConf) -> Self {
+        ConfigEntry::Directory(conf)
+    }
+}
+
+impl From<CloneDirectoryConf> for ConfigEntry {
+    fn from(conf: CloneDirectoryConf) -> Self {
+        ConfigEntry::CloneDirectory(conf)
+    }
+}
+
+impl From<FileConf> for ConfigEntry {
+    fn from(conf: FileConf) -> Self {
+        ConfigEntry::File(conf)
+    }
+}
+
+impl From<LinkConf> for ConfigEntry {
+    fn from(conf: LinkConf) -> Self {
+        ConfigEntry::Link(conf)
+    }
+}
+
+impl From<SymlinkConf> for ConfigEntry {
+    fn from(conf: SymlinkConf) -> Self {
+        ConfigEntry::Symlink(conf)
+    }
+}
+
+impl From<ArchiveConf> for ConfigEntry {
+    fn from(conf: ArchiveConf) -> Self {
+        ConfigEntry::Archive(conf)
+    }
 }
@@ -4,5 +4,5 @@ use super::config_entry::ConfigEntry;
 
 /// File System config structure to contains directories, files and links
 /// to execute tests with fs io operations
-#[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
 pub struct Configuration(pub Vec<ConfigEntry>);
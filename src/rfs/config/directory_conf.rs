@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 use super::config_entry::ConfigEntry;
+use super::timestamp::Timestamp;
 
 /// Structure for directory record in configuration
 /// for example:
@@ -41,11 +42,29 @@ use super::config_entry::ConfigEntry;
 ///     ]
 /// }
 /// ```
-#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
 pub struct DirectoryConf {
     /// A directory will be created with the given name.
     pub name: String,
 
     /// The directory content can contain a list of various entries.
     pub content: Vec<ConfigEntry>,
+
+    /// Optional permission bits (e.g. `0o555`) applied after the whole content is created,
+    /// so even a read-only or unreadable directory can be filled in.
+    #[serde(
+        default,
+        deserialize_with = "super::mode::deserialize",
+        serialize_with = "super::mode::serialize",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mode: Option<u32>,
+
+    /// Optional modification time, applied after the whole sandbox is built.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<Timestamp>,
+
+    /// Optional access time, applied after the whole sandbox is built.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accessed: Option<Timestamp>,
 }
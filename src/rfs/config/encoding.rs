@@ -0,0 +1,251 @@
+//! Serde helpers for the binary file content written as text.
+//!
+//! The content is decoded when the configuration is parsed, so the decoding errors are reported
+//! by the parser with the line and column of the value.
+
+use std::fmt::Write;
+
+use serde::de::{self, Deserializer, Unexpected, Visitor};
+use serde::Serializer;
+
+/// Implements the serde module for the encoding with its decoding and encoding functions.
+macro_rules! serde_encoding {
+    ($name:ident, $expecting:expr, $decode:ident, $encode:ident) => {
+        pub(crate) mod $name {
+            use super::*;
+
+            struct EncodedVisitor;
+
+            impl Visitor<'_> for EncodedVisitor {
+                type Value = Vec<u8>;
+
+                fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    formatter.write_str($expecting)
+                }
+
+                fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                    super::$decode(value).map_err(|problem| {
+                        E::invalid_value(Unexpected::Str(value), &problem.as_str())
+                    })
+                }
+            }
+
+            pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+            where
+                D: Deserializer<'de>,
+            {
+                deserializer.deserialize_str(EncodedVisitor)
+            }
+
+            pub(crate) fn serialize<S>(data: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+            {
+                serializer.serialize_str(&super::$encode(data))
+            }
+        }
+    };
+}
+
+serde_encoding!(base64, "a base64 string", decode_base64, encode_base64);
+serde_encoding!(hex, "a string of hex digit pairs", decode_hex, encode_hex);
+serde_encoding!(
+    escaped,
+    "a string with escapes like \\x00",
+    decode_escaped,
+    encode_escaped
+);
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Decodes the standard base64 padded with `=` to the complete groups of 4 characters.
+/// The whitespace is ignored, so long values can be split into lines.
+fn decode_base64(value: &str) -> Result<Vec<u8>, String> {
+    let symbols: Vec<char> = value.chars().filter(|c| !c.is_whitespace()).collect();
+    if !symbols.len().is_multiple_of(4) {
+        return Err(String::from(
+            "a base64 string of the complete length, padded with =",
+        ));
+    }
+    let padding = symbols.iter().rev().take_while(|&&c| c == '=').count();
+    if padding > 2 {
+        return Err(String::from(
+            "a base64 string with at most 2 padding characters",
+        ));
+    }
+
+    let mut data = vec![];
+    let mut bits: u32 = 0;
+    let mut bit_count = 0;
+    for &c in &symbols[..symbols.len() - padding] {
+        if c == '=' {
+            return Err(String::from("a base64 string with padding only at the end"));
+        }
+        let digit = BASE64_ALPHABET
+            .iter()
+            .position(|&symbol| symbol as char == c)
+            .ok_or_else(|| format!("a base64 string, {:?} is not a base64 character", c))?;
+
+        bits = (bits << 6) | digit as u32;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            data.push((bits >> bit_count) as u8);
+            bits &= (1 << bit_count) - 1;
+        }
+    }
+
+    Ok(data)
+}
+
+fn encode_base64(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (index, byte)| {
+            bits | (*byte as u32) << (16 - 8 * index)
+        });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[(bits >> (18 - 6 * index) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Decodes the pairs of hex digits, the whitespace is allowed only between the pairs.
+fn decode_hex(value: &str) -> Result<Vec<u8>, String> {
+    let mut data = vec![];
+    for group in value.split_whitespace() {
+        if !group.chars().count().is_multiple_of(2) {
+            return Err(format!(
+                "a string of hex digit pairs, {:?} has a pair which is not complete",
+                group
+            ));
+        }
+
+        let mut digits = group.chars();
+        while let (Some(high), Some(low)) = (digits.next(), digits.next()) {
+            let digit = |c: char| {
+                c.to_digit(16).ok_or_else(|| {
+                    format!("a string of hex digit pairs, {:?} is not a hex digit", c)
+                })
+            };
+            data.push((digit(high)? * 16 + digit(low)?) as u8);
+        }
+    }
+    Ok(data)
+}
+
+fn encode_hex(data: &[u8]) -> String {
+    data.iter().fold(String::new(), |mut encoded, byte| {
+        let _ = write!(encoded, "{:02x}", byte);
+        encoded
+    })
+}
+
+/// Decodes the text with the escapes `\xNN`, `\0`, `\n`, `\r`, `\t`, `\\`, `\"` and `\'`,
+/// other characters are taken as their UTF-8 bytes.
+fn decode_escaped(value: &str) -> Result<Vec<u8>, String> {
+    let mut data = vec![];
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buffer = [0; 4];
+            data.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+
+        let byte = match chars.next() {
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                u8::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| digits.len() == 2)
+                    .ok_or_else(|| {
+                        format!("an escaped string, \\x{} is not a hex escape", digits)
+                    })?
+            }
+            Some('0') => 0,
+            Some('n') => b'\n',
+            Some('r') => b'\r',
+            Some('t') => b'\t',
+            Some(c @ ('\\' | '"' | '\'')) => c as u8,
+            Some(c) => return Err(format!("an escaped string, \\{} is not an escape", c)),
+            None => return Err(String::from("an escaped string, it ends with \\")),
+        };
+        data.push(byte);
+    }
+    Ok(data)
+}
+
+fn encode_escaped(data: &[u8]) -> String {
+    data.iter().fold(String::new(), |mut encoded, byte| {
+        match byte {
+            b'\\' => encoded.push_str("\\\\"),
+            b'\n' => encoded.push_str("\\n"),
+            b'\t' => encoded.push_str("\\t"),
+            b' '..=b'~' => encoded.push(*byte as char),
+            _ => {
+                let _ = write!(encoded, "\\x{:02x}", byte);
+            }
+        }
+        encoded
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_should_round_trip() {
+        for data in [
+            &b""[..],
+            b"t",
+            b"te",
+            b"tes",
+            b"test",
+            &[0, 159, 146, 150, 255],
+        ] {
+            assert_eq!(decode_base64(&encode_base64(data)).as_deref(), Ok(data));
+        }
+        assert_eq!(encode_base64(b"test"), "dGVzdA==");
+        assert_eq!(decode_base64("dGVz\n dA==").as_deref(), Ok(&b"test"[..]));
+        assert_eq!(decode_base64("QQ==").as_deref(), Ok(&b"A"[..]));
+        for invalid in ["QQ", "QQ=", "Q===", "dGVzdA=x", "dG=zdA==", "dGVzd", "dG*z"] {
+            assert!(decode_base64(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn hex_should_round_trip() {
+        assert_eq!(encode_hex(&[0xde, 0xad, 0x00]), "dead00");
+        assert_eq!(
+            decode_hex("DE ad\n00").as_deref(),
+            Ok(&[0xde, 0xad, 0x00][..])
+        );
+        for invalid in ["dea", "4 1", "d ea0", "zz", "é0"] {
+            assert!(decode_hex(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn escaped_should_round_trip() {
+        let data = b"a\\b\n\x00\xff \xc3\xa9";
+        assert_eq!(
+            decode_escaped(&encode_escaped(data)).as_deref(),
+            Ok(&data[..])
+        );
+        assert_eq!(
+            decode_escaped(r#"\x00\0\t\"é"#).as_deref(),
+            Ok(&[0, 0, b'\t', b'"', 0xc3, 0xa9][..])
+        );
+        assert!(decode_escaped(r"\x0").is_err());
+        assert!(decode_escaped(r"\q").is_err());
+        assert!(decode_escaped("\\").is_err());
+    }
+}
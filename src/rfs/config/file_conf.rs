@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 use super::file_content::FileContent;
+use super::timestamp::Timestamp;
 
 /// The structure for file records in the configuration.
 /// The file can be configured in three ways: as an empty file,
@@ -82,8 +83,50 @@ use super::file_content::FileContent;
 ///   }
 /// }
 /// ```
-#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+///
+/// ## Permissions
+/// The optional **mode** sets the unix permission bits of the file
+/// after its content has been written, so read-only files can be configured too.
+/// The mode is octal, in JSON it is a string like `"0755"`.
+///
+/// ```yaml
+/// - !file
+///     name: run.sh
+///     content:
+///       !inline_text "#!/bin/sh"
+///     mode: 0o755
+/// ```
+///
+/// ## Timestamps
+/// The optional **modified** and **accessed** times can be set as an absolute RFC3339 date-time
+/// or as an offset relative to the sandbox creation time.
+///
+/// ```yaml
+/// - !file
+///     name: old.txt
+///     content: !empty
+///     modified: 2024-01-31T10:00:00Z
+///     accessed: -2d
+/// ```
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
 pub struct FileConf {
     pub name: String,
     pub content: FileContent,
+
+    /// Optional permission bits (e.g. `0o644`) applied after the content is written.
+    #[serde(
+        default,
+        deserialize_with = "super::mode::deserialize",
+        serialize_with = "super::mode::serialize",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mode: Option<u32>,
+
+    /// Optional modification time, applied after the whole sandbox is built.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<Timestamp>,
+
+    /// Optional access time, applied after the whole sandbox is built.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accessed: Option<Timestamp>,
 }
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 
-/// File content can be presented in fourth ways:
-#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+/// File content can be presented in the following ways:
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum FileContent {
     /// InlineBytes - by byte vector:
@@ -32,6 +32,70 @@ pub enum FileContent {
     ///                 !inline_text test
     /// ```
     InlineText(String),
+    /// InlineBase64 - by standard base64 string padded with `=`, the whitespace is ignored,
+    /// so the long value can be split into lines:
+    ///
+    /// ```yaml
+    /// - !directory
+    ///     name: base_container
+    ///     content:
+    ///         - !file
+    ///             name: test.bin
+    ///             content: !inline_base64 AJ+SlgA=
+    /// ```
+    ///
+    /// The value is decoded when the configuration is parsed, so it holds the decoded bytes.
+    InlineBase64(#[serde(with = "super::encoding::base64")] Vec<u8>),
+    /// InlineHex - by pairs of hex digits, the whitespace is allowed only between the pairs:
+    ///
+    /// ```yaml
+    /// - !directory
+    ///     name: base_container
+    ///     content:
+    ///         - !file
+    ///             name: test.bin
+    ///             content: !inline_hex "89 50 4e 47 0d 0a 1a 0a"
+    /// ```
+    ///
+    /// The value is decoded when the configuration is parsed, so it holds the decoded bytes.
+    InlineHex(#[serde(with = "super::encoding::hex")] Vec<u8>),
+    /// InlineEscaped - by string with the escapes `\xNN`, `\0`, `\n`, `\r`, `\t`,
+    /// `\\`, `\"` and `\'`, other characters are taken as their UTF-8 bytes:
+    ///
+    /// ```yaml
+    /// - !directory
+    ///     name: base_container
+    ///     content:
+    ///         - !file
+    ///             name: test.bin
+    ///             content: !inline_escaped 'header\x00\x01\xff'
+    /// ```
+    ///
+    /// The value is decoded when the configuration is parsed, so it holds the decoded bytes.
+    InlineEscaped(#[serde(with = "super::encoding::escaped")] Vec<u8>),
+    /// InlineTemplate - by string with the placeholders rendered when the sandbox is built,
+    /// so the content can refer to the sandbox itself:
+    ///
+    /// - `{{sandbox_root}}` - the absolute path of the sandbox directory,
+    /// - `{{entry_path}}` - the absolute path of the file itself,
+    /// - `{{env.NAME}}` - the value of the environment variable `NAME`,
+    /// - `{{name}}` - the variable from [`FsTesterOptions::variables`].
+    ///
+    /// The literal `{{` is escaped as `{{{{`.
+    ///
+    /// ```yaml
+    /// - !directory
+    ///     name: base_container
+    ///     content:
+    ///         - !file
+    ///             name: config.toml
+    ///             content: !inline_template |
+    ///                 data_dir = "{{sandbox_root}}/data"
+    ///                 user = "{{env.USER}}"
+    /// ```
+    ///
+    /// [`FsTesterOptions::variables`]: crate::FsTesterOptions::variables
+    InlineTemplate(String),
     /// OriginalFile - Retrieve from a real file using its path:
     ///
     /// ```yaml
@@ -44,6 +108,96 @@ pub enum FileContent {
     ///                 !original_file "test.txt"
     /// ```
     OriginalFile(String),
+    /// Random - the random bytes generated from the seed by the ChaCha8 algorithm, the same seed
+    /// gives the same content on every platform and with every version. The size is a number of bytes or a string with a unit
+    /// like `KB`, `KiB`, `MB`, `MiB`, `GB`, `GiB`:
+    ///
+    /// ```yaml
+    /// - !directory
+    ///     name: base_container
+    ///     content:
+    ///         - !file
+    ///             name: upload.bin
+    ///             content: !random { size: 512MiB, seed: 42 }
+    /// ```
+    ///
+    /// The generated content is written in chunks, so it is never held in memory as a whole.
+    Random {
+        #[serde(deserialize_with = "super::size::deserialize")]
+        size: u64,
+        #[serde(default)]
+        seed: u64,
+    },
+    /// Repeat - the text pattern repeated until the size is reached, the last copy can be cut:
+    ///
+    /// ```yaml
+    /// - !directory
+    ///     name: base_container
+    ///     content:
+    ///         - !file
+    ///             name: log.txt
+    ///             content: !repeat { pattern: "line\n", size: 1MB }
+    /// ```
+    Repeat {
+        pattern: String,
+        #[serde(deserialize_with = "super::size::deserialize")]
+        size: u64,
+    },
+    /// Zeros - the zero bytes of the size:
+    ///
+    /// ```yaml
+    /// - !directory
+    ///     name: base_container
+    ///     content:
+    ///         - !file
+    ///             name: disk.img
+    ///             content: !zeros { size: 2GiB }
+    /// ```
+    Zeros {
+        #[serde(deserialize_with = "super::size::deserialize")]
+        size: u64,
+    },
+    /// Sparse - the file of the logical size with the data extents written at their offsets,
+    /// the gaps between the extents are left as holes, so the file takes little disk space
+    /// on the file systems supporting them. The extents may be listed in any order, they are
+    /// sorted by the offset; they should not overlap and should fit into the size, they can not
+    /// be sparse themselves:
+    ///
+    /// ```yaml
+    /// - !directory
+    ///     name: base_container
+    ///     content:
+    ///         - !file
+    ///             name: disk.img
+    ///             content: !sparse
+    ///                 size: 1GiB
+    ///                 extents:
+    ///                     - offset: 0
+    ///                       content: !inline_text header
+    ///                     - offset: 512MiB
+    ///                       content: !random { size: 1MiB, seed: 1 }
+    /// ```
+    Sparse {
+        #[serde(deserialize_with = "super::size::deserialize")]
+        size: u64,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        extents: Vec<SparseExtent>,
+    },
+    /// Gzip - the gzip compressed content, e.g. the original file or the inline base64,
+    /// which is decompressed when the file is written:
+    ///
+    /// ```yaml
+    /// - !directory
+    ///     name: base_container
+    ///     content:
+    ///         - !file
+    ///             name: access.log
+    ///             content: !gzip
+    ///                 content: !original_file "fixtures/access.log.gz"
+    /// ```
+    ///
+    /// The content is decompressed in chunks, so it is never held in memory as a whole.
+    Gzip { content: Box<FileContent> },
     /// or simply Empty
     ///
     /// ```yaml
@@ -53,5 +207,59 @@ pub enum FileContent {
     ///         - !file
     ///             name: test.txt
     ///             content: !empty
+    #[default]
     Empty,
 }
+
+/// The data extent of the sparse file content.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+pub struct SparseExtent {
+    /// The offset of the data in the file, a number of bytes or a size with a unit.
+    #[serde(deserialize_with = "super::size::deserialize")]
+    pub offset: u64,
+
+    /// The data written at the offset.
+    pub content: FileContent,
+}
+
+impl FileContent {
+    /// The path of the original file which the content is read from,
+    /// also if it is decompressed.
+    pub(crate) fn original_file(&self) -> Option<&str> {
+        match self {
+            FileContent::OriginalFile(file_path) => Some(file_path),
+            FileContent::Gzip { content } => content.original_file(),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for FileContent {
+    fn from(text: &str) -> Self {
+        FileContent::InlineText(text.to_string())
+    }
+}
+
+impl From<String> for FileContent {
+    fn from(text: String) -> Self {
+        FileContent::InlineText(text)
+    }
+}
+
+impl From<Vec<u8>> for FileContent {
+    fn from(data: Vec<u8>) -> Self {
+        FileContent::InlineBytes(data)
+    }
+}
+
+impl From<&[u8]> for FileContent {
+    fn from(data: &[u8]) -> Self {
+        FileContent::InlineBytes(data.to_vec())
+    }
+}
+
+impl<const N: usize> From<&[u8; N]> for FileContent {
+    fn from(data: &[u8; N]) -> Self {
+        FileContent::InlineBytes(data.to_vec())
+    }
+}
@@ -4,6 +4,11 @@ use serde::{Deserialize, Serialize};
 ///
 /// The link may refer to another test file.
 ///
+/// The target is resolved from the current directory, not from the sandbox, like
+/// the `original_file` content, so it is a source file outside of the sandbox by design.
+/// That is why the hard links have no `allow_outside` opt-in of the symbolic links:
+/// the LINKS_ALLOWED environment variable is the opt-in for every hard link.
+///
 /// ### yaml
 ///
 /// ```yaml
@@ -0,0 +1,124 @@
+//! The `location` module finds the entries of the parsed configuration in its source text,
+//! so the build errors can point to the approximate line of the offending entry.
+use std::path::Path;
+
+const NAME_KEY: &str = "name";
+
+/// Returns the one-based line and column of the `name` key of the entry with the given path,
+/// e.g. `root/sub/test.mp3`, in the YAML or JSON configuration string.
+///
+/// The entries are looked up by their names one after another in the text, not in the parsed
+/// document, so the location is approximate: it may point to a nested entry with the same name
+/// as its later sibling, or to the `name: <value>` text inside an inline content.
+pub(crate) fn locate_entry(config_str: &str, path: &Path) -> Option<(usize, usize)> {
+    let mut offset = 0;
+    let mut key_offset = None;
+    for component in path.components() {
+        let name = component.as_os_str().to_str()?;
+        let (found, value_end) = find_name(config_str, offset, name)?;
+        key_offset = Some(found);
+        offset = value_end;
+    }
+
+    let key_offset = key_offset?;
+    let before = &config_str[..key_offset];
+    let line_start = before.rfind('\n').map_or(0, |position| position + 1);
+    let line = before.matches('\n').count() + 1;
+    let column = before[line_start..].chars().count() + 1;
+    Some((line, column))
+}
+
+/// Finds the next `name` key with the given value starting from the offset.
+/// Returns the offset of the key and the offset after its value.
+fn find_name(config_str: &str, from: usize, name: &str) -> Option<(usize, usize)> {
+    let mut start = from;
+    while let Some(found) = config_str[start..].find(NAME_KEY) {
+        let key = start + found;
+        start = key + NAME_KEY.len();
+
+        let is_quoted = config_str[..key].ends_with('"');
+        let key_start = if is_quoted { key - 1 } else { key };
+        let is_key = config_str[..key_start]
+            .chars()
+            .next_back()
+            .is_none_or(|c| c.is_whitespace() || c == '{' || c == ',');
+        if !is_key {
+            continue;
+        }
+
+        let mut rest = &config_str[start..];
+        if is_quoted {
+            let Some(unquoted) = rest.strip_prefix('"') else {
+                continue;
+            };
+            rest = unquoted;
+        }
+        let Some(value) = rest.trim_start_matches([' ', '\t']).strip_prefix(':') else {
+            continue;
+        };
+        let value = value.trim_start_matches([' ', '\t']);
+        let value_start = config_str.len() - value.len();
+
+        let (value, value_len) = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => match value[1..].find(quote) {
+                Some(end) => (&value[1..end + 1], end + 2),
+                None => continue,
+            },
+            _ => {
+                let end = value
+                    .find(['\n', '\r', ',', '}', '#'])
+                    .unwrap_or(value.len());
+                (value[..end].trim_end(), end)
+            }
+        };
+
+        if value == name {
+            return Some((key_start, value_start + value_len));
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const YAML_CONFIG: &str = "
+- !directory
+    name: root
+    content:
+      - !directory
+          name: sub
+          content:
+            - !file
+                name: test.txt
+                content: !empty
+      - !file
+          name: \"test.txt\"
+          content: !empty
+";
+
+    #[test]
+    fn yaml_entries_should_be_located_by_path() {
+        assert_eq!(locate_entry(YAML_CONFIG, Path::new("root")), Some((3, 5)));
+        assert_eq!(
+            locate_entry(YAML_CONFIG, Path::new("root/sub/test.txt")),
+            Some((9, 17))
+        );
+        assert_eq!(
+            locate_entry(YAML_CONFIG, Path::new("root/missing.txt")),
+            None
+        );
+    }
+
+    #[test]
+    fn json_entries_should_be_located_by_path() {
+        let config_str = "[{\"directory\":{\"name\":\"root\",\"content\":[\n  {\"file\":{\"filename\":\"x\",\"name\": \"test.txt\"}}]}}]";
+
+        assert_eq!(
+            locate_entry(config_str, Path::new("root/test.txt")),
+            Some((2, 27))
+        );
+    }
+}
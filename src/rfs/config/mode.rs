@@ -0,0 +1,64 @@
+//! Serde helpers for the unix permission bits of configuration entries.
+//!
+//! The mode is always written with octal digits. YAML reads the plain scalar as it is written,
+//! so `mode: 0o644`, `mode: 0644` and `mode: 644` are the same. JSON has no octal notation,
+//! so the mode is an octal string there (`"mode": "0644"` or `"mode": "0o644"`), and a JSON
+//! number is rejected, because `"mode": 644` would silently mean decimal 644, which is `0o1204`.
+
+use serde::de::{self, Deserializer, Unexpected, Visitor};
+use serde::Serializer;
+use std::fmt;
+
+/// The largest mode: the permission bits with the setuid, setgid and sticky bits.
+const MAX_MODE: u32 = 0o7777;
+
+struct ModeVisitor;
+
+impl<'de> Visitor<'de> for ModeVisitor {
+    type Value = Option<u32>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("permission bits as an octal string like \"0644\"")
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    /// The mode is requested as a string, so YAML passes the plain scalar as it is written
+    /// instead of converting it to a number, and JSON rejects numbers.
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_str(ModeVisitor)
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        let digits = value.strip_prefix("0o").unwrap_or(value);
+        u32::from_str_radix(digits, 8)
+            .ok()
+            .filter(|mode| *mode <= MAX_MODE)
+            .map(Some)
+            .ok_or_else(|| E::invalid_value(Unexpected::Str(value), &self))
+    }
+}
+
+pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_option(ModeVisitor)
+}
+
+/// Writes the mode as an octal string like `0o644`, which is read back in every format.
+pub(crate) fn serialize<S>(mode: &Option<u32>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    match mode {
+        Some(mode) => serializer.serialize_str(&format!("0o{:o}", mode)),
+        None => serializer.serialize_none(),
+    }
+}
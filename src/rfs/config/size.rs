@@ -0,0 +1,84 @@
+//! Serde helpers for the sizes of generated file content.
+//!
+//! The size can be a number of bytes (`size: 1024`) or a string with a unit
+//! (`size: 512MiB`, `"size": "2 GB"`). Decimal units (`KB`, `MB`, `GB`, `TB`) are powers of 1000,
+//! binary units (`KiB`, `MiB`, `GiB`, `TiB`) are powers of 1024, the units are case-insensitive.
+
+use serde::de::{self, Deserializer, Unexpected, Visitor};
+use std::fmt;
+
+const UNITS: [(&str, u64); 9] = [
+    ("b", 1),
+    ("kb", 1000),
+    ("kib", 1 << 10),
+    ("mb", 1000 * 1000),
+    ("mib", 1 << 20),
+    ("gb", 1000 * 1000 * 1000),
+    ("gib", 1 << 30),
+    ("tb", 1000 * 1000 * 1000 * 1000),
+    ("tib", 1 << 40),
+];
+
+/// Parses the size like `512MiB` into the number of bytes.
+pub(crate) fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let unit_start = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let number: u64 = value[..unit_start].parse().ok()?;
+    let unit = value[unit_start..].trim_start().to_ascii_lowercase();
+    if unit.is_empty() {
+        return Some(number);
+    }
+
+    let (_, multiplier) = UNITS.iter().find(|(name, _)| *name == unit)?;
+    number.checked_mul(*multiplier)
+}
+
+struct SizeVisitor;
+
+impl Visitor<'_> for SizeVisitor {
+    type Value = u64;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a number of bytes or a size with a unit like 512MiB")
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+        Ok(value)
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Self::Value, E> {
+        u64::try_from(value).map_err(|_| E::invalid_value(Unexpected::Signed(value), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        parse_size(value).ok_or_else(|| E::invalid_value(Unexpected::Str(value), &self))
+    }
+}
+
+pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_any(SizeVisitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_should_be_parsed_with_units() {
+        assert_eq!(parse_size("1024"), Some(1024));
+        assert_eq!(parse_size("10B"), Some(10));
+        assert_eq!(parse_size("2 KB"), Some(2000));
+        assert_eq!(parse_size("512MiB"), Some(512 << 20));
+        assert_eq!(parse_size("2gib"), Some(2 << 30));
+        assert_eq!(parse_size("1TiB"), Some(1 << 40));
+        assert_eq!(parse_size("1.5GB"), None);
+        assert_eq!(parse_size("12XB"), None);
+        assert_eq!(parse_size("MiB"), None);
+        assert_eq!(parse_size("99999999999TiB"), None);
+    }
+}
@@ -0,0 +1,71 @@
+use std::path::{Component, Path};
+
+use serde::{Deserialize, Serialize};
+
+/// The structure of the configuration symbolic link
+///
+/// Unlike the hard link, the symbolic link only stores the path to its target.
+/// The target may be relative or absolute, may point to a file or a directory,
+/// and does not have to exist at all, so dangling and looping links can be configured too.
+/// A relative target is resolved from the directory containing the link.
+/// The target outside of the sandbox should be allowed explicitly by `allow_outside`.
+///
+/// ### yaml
+///
+/// ```yaml
+/// - !symlink
+///     name: test_symlink
+///     target: test.txt
+/// ```
+///
+/// ```yaml
+/// - !symlink
+///     name: hosts_link
+///     target: /etc/hosts
+///     allow_outside: true
+/// ```
+///
+/// ### json
+/// ```json
+/// "symlink": {
+///   "name": "test_symlink",
+///   "target": "test.txt"
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone, Default)]
+pub struct SymlinkConf {
+    pub name: String,
+    pub target: String,
+
+    /// Allows the target which is absolute or leads outside of the sandbox directory.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_outside: bool,
+}
+
+impl SymlinkConf {
+    /// Returns true if the target leads outside of the sandbox directory,
+    /// when the link is placed `depth` directories below the sandbox directory.
+    /// The target is resolved lexically, the symbolic links on its way are not followed.
+    pub(crate) fn target_escapes(&self, depth: usize) -> bool {
+        target_escapes(Path::new(&self.target), depth)
+    }
+}
+
+/// Returns true if the symbolic link target leads outside of the sandbox directory,
+/// see [`SymlinkConf::target_escapes`]. The extracted archives check their links with it too.
+pub(crate) fn target_escapes(target: &Path, depth: usize) -> bool {
+    let mut depth = depth;
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => match depth.checked_sub(1) {
+                Some(parent_depth) => depth = parent_depth,
+                None => return true,
+            },
+            Component::RootDir | Component::Prefix(_) => return true,
+        }
+    }
+
+    false
+}
@@ -0,0 +1,158 @@
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+/// The timestamp of a configuration entry (modification or access time).
+///
+/// It can be set as an absolute RFC3339 date-time or as an offset
+/// relative to the moment when the sandbox is built:
+///
+/// ```yaml
+/// - !file
+///     name: old.txt
+///     content: !empty
+///     modified: 2024-01-31T10:00:00Z
+///     accessed: -2d
+/// ```
+///
+/// The relative offset starts with a `-` (in the past) or `+` (in the future) sign
+/// followed by a duration, e.g. `-2d`, `-1h 30m`, `+15min`.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum Timestamp {
+    /// An exact point in time.
+    Absolute(SystemTime),
+
+    /// A point in time before the sandbox creation.
+    Ago(Duration),
+
+    /// A point in time after the sandbox creation.
+    FromNow(Duration),
+}
+
+impl Timestamp {
+    /// Calculates the exact point in time, using `now` as the base for relative offsets.
+    pub fn resolve(&self, now: SystemTime) -> SystemTime {
+        match self {
+            Timestamp::Absolute(time) => *time,
+            Timestamp::Ago(offset) => now.checked_sub(*offset).unwrap_or(SystemTime::UNIX_EPOCH),
+            Timestamp::FromNow(offset) => now + *offset,
+        }
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let parse_offset = |offset: &str| {
+            humantime::parse_duration(offset.trim())
+                .map_err(|err| format!("invalid relative timestamp \"{}\": {}", value, err))
+        };
+
+        if let Some(offset) = value.strip_prefix('-') {
+            parse_offset(offset).map(Timestamp::Ago)
+        } else if let Some(offset) = value.strip_prefix('+') {
+            parse_offset(offset).map(Timestamp::FromNow)
+        } else {
+            humantime::parse_rfc3339_weak(value)
+                .map(Timestamp::Absolute)
+                .map_err(|err| format!("invalid RFC3339 timestamp \"{}\": {}", value, err))
+        }
+    }
+}
+
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Timestamp::Absolute(time) => write!(f, "{}", humantime::format_rfc3339(*time)),
+            Timestamp::Ago(offset) => write!(f, "-{}", humantime::format_duration(*offset)),
+            Timestamp::FromNow(offset) => write!(f, "+{}", humantime::format_duration(*offset)),
+        }
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+struct TimestampVisitor;
+
+impl Visitor<'_> for TimestampVisitor {
+    type Value = Timestamp;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an RFC3339 date-time or a relative offset like -2d")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+        value.parse().map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(TimestampVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_absolute_timestamp() {
+        let timestamp: Timestamp = "2024-01-31T10:00:00Z".parse().unwrap();
+
+        assert_eq!(
+            timestamp,
+            Timestamp::Absolute(SystemTime::UNIX_EPOCH + Duration::from_secs(1_706_695_200))
+        );
+    }
+
+    #[test]
+    fn test_parse_relative_timestamps() {
+        assert_eq!(
+            "-2d".parse::<Timestamp>().unwrap(),
+            Timestamp::Ago(Duration::from_secs(2 * 24 * 3600))
+        );
+        assert_eq!(
+            "+1h 30m".parse::<Timestamp>().unwrap(),
+            Timestamp::FromNow(Duration::from_secs(5400))
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_timestamp() {
+        assert!("yesterday".parse::<Timestamp>().is_err());
+        assert!("-2 parsecs".parse::<Timestamp>().is_err());
+    }
+
+    #[test]
+    fn test_resolve_relative_timestamps() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+
+        assert_eq!(
+            Timestamp::Ago(Duration::from_secs(100)).resolve(now),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(900)
+        );
+        assert_eq!(
+            Timestamp::FromNow(Duration::from_secs(100)).resolve(now),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1100)
+        );
+    }
+
+    #[test]
+    fn test_timestamp_serialization_round_trip() {
+        for value in ["2024-01-31T10:00:00Z", "-2days", "+1h 30m"] {
+            let timestamp: Timestamp = value.parse().unwrap();
+            let yaml = serde_yaml::to_string(&timestamp).unwrap();
+
+            assert_eq!(serde_yaml::from_str::<Timestamp>(&yaml).unwrap(), timestamp);
+        }
+    }
+}
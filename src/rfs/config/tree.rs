@@ -0,0 +1,241 @@
+//! The fluent builder of configurations, so a sandbox can be described in Rust code
+//! and checked by the compiler instead of parsing YAML or JSON strings at runtime.
+use super::archive_conf::ArchiveConf;
+use super::clone_directory_conf::CloneDirectoryConf;
+use super::config_entry::ConfigEntry;
+use super::configuration::Configuration;
+use super::directory_conf::DirectoryConf;
+use super::file_conf::FileConf;
+use super::file_content::FileContent;
+use super::link_conf::LinkConf;
+use super::symlink_conf::SymlinkConf;
+use super::timestamp::Timestamp;
+
+/// The entry point of the configuration builder.
+///
+/// ```rust
+/// # use rfs_tester::config::Tree;
+/// # use rfs_tester::FsTester;
+/// let config = Tree::dir("test_tree_doc")
+///     .file("a.txt", "hi")
+///     .file("b.bin", vec![0u8, 1, 2])
+///     .dir("sub", |d| d.file("c.txt", "nested").symlink("a_link", "../a.txt"))
+///     .build();
+///
+/// let tester = FsTester::from_config(config, ".").unwrap();
+/// tester.perform_fs_test(|dirname| {
+///     let content = std::fs::read_to_string(std::path::Path::new(dirname).join("sub/c.txt"))?;
+///     assert_eq!(content, "nested");
+///     Ok(())
+/// });
+/// ```
+pub struct Tree;
+
+impl Tree {
+    /// Starts the configuration with the root directory.
+    pub fn dir(name: impl Into<String>) -> DirBuilder {
+        DirBuilder {
+            conf: DirectoryConf {
+                name: name.into(),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Creates the configuration with the root directory cloned from the source directory.
+    pub fn clone_dir(name: impl Into<String>, source: impl Into<String>) -> Configuration {
+        Configuration(vec![ConfigEntry::CloneDirectory(CloneDirectoryConf {
+            name: name.into(),
+            source: source.into(),
+            ..Default::default()
+        })])
+    }
+}
+
+/// The builder of a directory entry and its content.
+#[derive(Debug, Clone)]
+pub struct DirBuilder {
+    conf: DirectoryConf,
+}
+
+impl DirBuilder {
+    /// Adds a file with the given content. Strings become `inline_text`
+    /// and byte vectors or slices become `inline_bytes`.
+    pub fn file(self, name: impl Into<String>, content: impl Into<FileContent>) -> Self {
+        self.entry(FileConf {
+            name: name.into(),
+            content: content.into(),
+            ..Default::default()
+        })
+    }
+
+    /// Adds a nested directory filled in by the `build` closure.
+    pub fn dir(
+        self,
+        name: impl Into<String>,
+        build: impl FnOnce(DirBuilder) -> DirBuilder,
+    ) -> Self {
+        let nested = build(Tree::dir(name));
+        self.entry(nested)
+    }
+
+    /// Adds a directory with the copy of the source directory content.
+    pub fn clone_dir(self, name: impl Into<String>, source: impl Into<String>) -> Self {
+        self.entry(CloneDirectoryConf {
+            name: name.into(),
+            source: source.into(),
+            ..Default::default()
+        })
+    }
+
+    /// Adds a hard link to the target file. It requires the LINKS_ALLOWED environment variable.
+    pub fn link(self, name: impl Into<String>, target: impl Into<String>) -> Self {
+        self.entry(LinkConf {
+            name: name.into(),
+            target: target.into(),
+        })
+    }
+
+    /// Adds a symbolic link to the target path.
+    pub fn symlink(self, name: impl Into<String>, target: impl Into<String>) -> Self {
+        self.entry(SymlinkConf {
+            name: name.into(),
+            target: target.into(),
+            ..Default::default()
+        })
+    }
+
+    /// Adds a directory with the extracted content of the tar, tar.gz or zip archive.
+    /// Byte vectors or slices become `inline_bytes`, use `FileContent::OriginalFile`
+    /// for the archive file.
+    pub fn archive(self, name: impl Into<String>, source: impl Into<FileContent>) -> Self {
+        self.entry(ArchiveConf {
+            name: name.into(),
+            source: source.into(),
+        })
+    }
+
+    /// Adds any configuration entry, e.g. a file with the permission bits or timestamps.
+    pub fn entry(mut self, entry: impl Into<ConfigEntry>) -> Self {
+        self.conf.content.push(entry.into());
+        self
+    }
+
+    /// Sets the permission bits of the directory.
+    pub fn mode(mut self, mode: u32) -> Self {
+        self.conf.mode = Some(mode);
+        self
+    }
+
+    /// Sets the modification time of the directory.
+    pub fn modified(mut self, modified: Timestamp) -> Self {
+        self.conf.modified = Some(modified);
+        self
+    }
+
+    /// Sets the access time of the directory.
+    pub fn accessed(mut self, accessed: Timestamp) -> Self {
+        self.conf.accessed = Some(accessed);
+        self
+    }
+
+    /// Finishes the configuration with this directory as the root entry.
+    pub fn build(self) -> Configuration {
+        Configuration(vec![ConfigEntry::Directory(self.conf)])
+    }
+
+    /// Returns the built directory entry.
+    pub fn into_conf(self) -> DirectoryConf {
+        self.conf
+    }
+}
+
+impl From<DirBuilder> for ConfigEntry {
+    fn from(builder: DirBuilder) -> Self {
+        ConfigEntry::Directory(builder.conf)
+    }
+}
+
+impl From<DirBuilder> for Configuration {
+    fn from(builder: DirBuilder) -> Self {
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::FsTester;
+
+    #[test]
+    fn builder_should_produce_the_same_config_as_yaml() {
+        let expected = FsTester::parse_config(
+            r#"
+            - !directory
+                name: root
+                mode: 0o755
+                content:
+                  - !file
+                      name: a.txt
+                      content: !inline_text hi
+                  - !file
+                      name: b.bin
+                      content: !inline_bytes [1, 2]
+                  - !directory
+                      name: sub
+                      accessed: -2d
+                      content:
+                        - !symlink
+                            name: a_link
+                            target: ../a.txt
+                        - !link
+                            name: hard_link
+                            target: test.txt
+                  - !clone_directory
+                      name: copy
+                      source: src
+                  - !file
+                      name: locked.txt
+                      content: !empty
+                      mode: 0o400
+            "#,
+        )
+        .unwrap();
+
+        let config = Tree::dir("root")
+            .mode(0o755)
+            .file("a.txt", "hi")
+            .file("b.bin", b"\x01\x02")
+            .dir("sub", |d| {
+                d.accessed(Timestamp::Ago(Duration::from_secs(2 * 24 * 3600)))
+                    .symlink("a_link", "../a.txt")
+                    .link("hard_link", "test.txt")
+            })
+            .clone_dir("copy", "src")
+            .entry(FileConf {
+                name: String::from("locked.txt"),
+                mode: Some(0o400),
+                ..Default::default()
+            })
+            .build();
+
+        assert_eq!(config, expected);
+    }
+
+    #[test]
+    fn clone_dir_should_produce_the_clone_root() {
+        assert_eq!(
+            Tree::clone_dir("copy", "src"),
+            FsTester::parse_config(
+                "
+                - !clone_directory
+                    name: copy
+                    source: src
+                "
+            )
+            .unwrap()
+        );
+    }
+}
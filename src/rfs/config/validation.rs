@@ -0,0 +1,431 @@
+//! The `validation` module checks the configuration before anything is created on disk,
+//! so all problems are reported at once instead of failing in the middle of the build.
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::rfs::clone_filter::CloneFilter;
+
+use super::config_entry::ConfigEntry;
+use super::configuration::Configuration;
+use super::file_content::FileContent;
+
+/// The problem of a configuration entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IssueKind {
+    /// The entry name is empty.
+    EmptyName,
+
+    /// The entry name is not a single path component, e.g. it contains `/`.
+    InvalidName,
+
+    /// The entry name leads outside of its directory, e.g. it is `..` or an absolute path.
+    EscapesSandbox,
+
+    /// The target of the symbolic link leads outside of the sandbox,
+    /// but it is not allowed by `allow_outside`.
+    SymlinkOutsideSandbox(PathBuf),
+
+    /// Another entry of the same directory has the same name.
+    DuplicateName,
+
+    /// The `original_file` of the file entry is not an existing file.
+    MissingOriginalFile(PathBuf),
+
+    /// The repeated pattern of the generated file content is empty.
+    EmptyPattern,
+
+    /// The extent of the sparse file content at the offset overlaps another extent,
+    /// exceeds the file size or is sparse itself. The extents of unknown length,
+    /// like the decompressed ones, are checked when they are written.
+    InvalidExtent(u64),
+
+    /// The target of the hard link is not an existing file.
+    MissingLinkTarget(PathBuf),
+
+    /// The source of the cloned directory is not an existing directory.
+    MissingCloneSource(PathBuf),
+
+    /// The include or exclude pattern of the cloned directory is invalid.
+    InvalidPattern(String),
+
+    /// The removed path of the cloned directory is not a relative path inside of the clone.
+    InvalidRemovePath(PathBuf),
+}
+
+impl Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IssueKind::EmptyName => write!(f, "the name is empty"),
+            IssueKind::InvalidName => write!(f, "the name should be a single path component"),
+            IssueKind::EscapesSandbox => write!(f, "the name leads outside of the sandbox"),
+            IssueKind::SymlinkOutsideSandbox(target) => write!(
+                f,
+                "the symlink target {:?} leads outside of the sandbox, set allow_outside to permit it",
+                target
+            ),
+            IssueKind::DuplicateName => write!(f, "the name is used more than once"),
+            IssueKind::MissingOriginalFile(path) => {
+                write!(f, "the original file {:?} is not found", path)
+            }
+            IssueKind::EmptyPattern => write!(f, "the repeated pattern is empty"),
+            IssueKind::InvalidExtent(offset) => write!(
+                f,
+                "the sparse extent at offset {} overlaps another extent or exceeds the file size",
+                offset
+            ),
+            IssueKind::MissingLinkTarget(path) => {
+                write!(f, "the link target {:?} is not found", path)
+            }
+            IssueKind::MissingCloneSource(path) => {
+                write!(f, "the source directory {:?} is not found", path)
+            }
+            IssueKind::InvalidPattern(err) => write!(f, "invalid pattern: {}", err),
+            IssueKind::InvalidRemovePath(path) => {
+                write!(f, "the removed path {:?} leads outside of the clone", path)
+            }
+        }
+    }
+}
+
+impl IssueKind {
+    /// Returns true if the problem is an attempt to create or change an entry outside of the sandbox.
+    pub fn is_sandbox_escape(&self) -> bool {
+        matches!(
+            self,
+            IssueKind::EscapesSandbox
+                | IssueKind::SymlinkOutsideSandbox(_)
+                | IssueKind::InvalidRemovePath(_)
+        )
+    }
+}
+
+/// The problem found in the configuration with the path of the entry,
+/// which starts from the root entry name, e.g. `root/sub/file.txt`.
+/// The entry with the empty name is named by its index in the parent content, e.g. `root/[2]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    pub path: PathBuf,
+    pub kind: IssueKind,
+}
+
+impl Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.kind)
+    }
+}
+
+impl Configuration {
+    /// Walks the configuration and returns all found problems. The configuration
+    /// without problems returns an empty list. Symbolic link targets are only checked
+    /// to stay inside of the sandbox, because dangling symbolic links are allowed.
+    /// Hard link targets are source files resolved from the current directory, like
+    /// `original_file`, so they are not checked to stay inside of the sandbox: the link
+    /// itself is created inside, and the LINKS_ALLOWED environment variable permits it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use rfs_tester::config::{IssueKind, Tree};
+    /// let config = Tree::dir("root").file("a.txt", "").file("a.txt", "").build();
+    /// let issues = config.validate();
+    ///
+    /// assert_eq!(issues.len(), 1);
+    /// assert_eq!(issues[0].path, std::path::Path::new("root/a.txt"));
+    /// assert_eq!(issues[0].kind, IssueKind::DuplicateName);
+    /// ```
+    pub fn validate(&self) -> Vec<ConfigIssue> {
+        let mut issues = vec![];
+        Self::validate_content(&self.0, Path::new(""), &mut issues);
+        issues
+    }
+
+    fn validate_content(content: &[ConfigEntry], parent: &Path, issues: &mut Vec<ConfigIssue>) {
+        let mut names = HashSet::new();
+        for (index, entry) in content.iter().enumerate() {
+            let path = Self::entry_path(parent, index, entry);
+            if !names.insert(entry.name()) {
+                issues.push(ConfigIssue {
+                    path: path.clone(),
+                    kind: IssueKind::DuplicateName,
+                });
+            }
+            Self::validate_entry(entry, parent, path, issues);
+        }
+    }
+
+    /// The path of the entry, the entry without the name is named by its index.
+    fn entry_path(parent: &Path, index: usize, entry: &ConfigEntry) -> PathBuf {
+        if entry.name().is_empty() {
+            parent.join(format!("[{}]", index))
+        } else {
+            parent.join(entry.name())
+        }
+    }
+
+    fn validate_entry(
+        entry: &ConfigEntry,
+        parent: &Path,
+        path: PathBuf,
+        issues: &mut Vec<ConfigIssue>,
+    ) {
+        let mut push = |kind| {
+            issues.push(ConfigIssue {
+                path: path.clone(),
+                kind,
+            })
+        };
+
+        let mut components = Path::new(entry.name()).components();
+        if entry.name().is_empty() {
+            push(IssueKind::EmptyName);
+        } else if Path::new(entry.name()).components().any(|component| {
+            matches!(
+                component,
+                Component::ParentDir | Component::RootDir | Component::Prefix(_)
+            )
+        }) {
+            push(IssueKind::EscapesSandbox);
+        } else if !matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        ) || entry.name().contains(['/', '\\'])
+        {
+            push(IssueKind::InvalidName);
+        }
+
+        match entry {
+            ConfigEntry::Directory(conf) => Self::validate_content(&conf.content, &path, issues),
+            ConfigEntry::CloneDirectory(conf) => {
+                if !Path::new(&conf.source).is_dir() {
+                    push(IssueKind::MissingCloneSource(PathBuf::from(&conf.source)));
+                }
+                if let Err(err) = CloneFilter::new(conf) {
+                    push(IssueKind::InvalidPattern(err.to_string()));
+                }
+                for removed in &conf.remove {
+                    let is_inside = Path::new(removed).components().all(|component| {
+                        matches!(component, Component::Normal(_) | Component::CurDir)
+                    });
+                    if !is_inside {
+                        push(IssueKind::InvalidRemovePath(PathBuf::from(removed)));
+                    }
+                }
+                Self::validate_content(&conf.content, &path, issues);
+            }
+            ConfigEntry::File(conf) => Self::validate_file_content(&conf.content, &mut push),
+            ConfigEntry::Archive(conf) => Self::validate_file_content(&conf.source, &mut push),
+            ConfigEntry::Link(conf) => {
+                if !fs::metadata(&conf.target).is_ok_and(|metadata| metadata.is_file()) {
+                    push(IssueKind::MissingLinkTarget(PathBuf::from(&conf.target)));
+                }
+            }
+            ConfigEntry::Symlink(conf) => {
+                // The parent path starts from the sandbox directory itself.
+                let depth = parent.components().count().saturating_sub(1);
+                if !conf.allow_outside && conf.target_escapes(depth) {
+                    push(IssueKind::SymlinkOutsideSandbox(PathBuf::from(
+                        &conf.target,
+                    )));
+                }
+            }
+        }
+    }
+
+    fn validate_file_content(content: &FileContent, push: &mut impl FnMut(IssueKind)) {
+        match content {
+            FileContent::OriginalFile(file_path)
+                if !fs::metadata(file_path).is_ok_and(|metadata| metadata.is_file()) =>
+            {
+                push(IssueKind::MissingOriginalFile(PathBuf::from(file_path)))
+            }
+            FileContent::Repeat { pattern, size } if pattern.is_empty() && *size > 0 => {
+                push(IssueKind::EmptyPattern)
+            }
+            FileContent::Sparse { size, extents } => {
+                // The extents are written in the order of their offsets.
+                let mut extents = extents.iter().collect::<Vec<_>>();
+                extents.sort_by_key(|extent| extent.offset);
+                let mut data_end = 0;
+                for extent in extents {
+                    let extent_end = Self::content_len(&extent.content)
+                        .map(|len| extent.offset.saturating_add(len));
+                    if matches!(extent.content, FileContent::Sparse { .. })
+                        || extent.offset < data_end
+                        || extent_end.is_some_and(|extent_end| extent_end > *size)
+                    {
+                        push(IssueKind::InvalidExtent(extent.offset));
+                    }
+                    data_end = extent_end.unwrap_or(extent.offset).max(data_end);
+
+                    Self::validate_file_content(&extent.content, push);
+                }
+            }
+            FileContent::Gzip { content } => Self::validate_file_content(content, push),
+            _ => {}
+        }
+    }
+
+    /// The length of the file content, if it is known before the file is created.
+    fn content_len(content: &FileContent) -> Option<u64> {
+        match content {
+            FileContent::InlineBytes(data)
+            | FileContent::InlineBase64(data)
+            | FileContent::InlineHex(data)
+            | FileContent::InlineEscaped(data) => Some(data.len() as u64),
+            FileContent::InlineText(text) => Some(text.len() as u64),
+            FileContent::OriginalFile(file_path) => {
+                fs::metadata(file_path).ok().map(|metadata| metadata.len())
+            }
+            FileContent::Empty => Some(0),
+            FileContent::Random { size, .. }
+            | FileContent::Repeat { size, .. }
+            | FileContent::Zeros { size }
+            | FileContent::Sparse { size, .. } => Some(*size),
+            // The lengths are only known after the decompression or the rendering.
+            FileContent::Gzip { .. } | FileContent::InlineTemplate(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rfs::config::clone_directory_conf::CloneDirectoryConf;
+    use crate::rfs::config::file_content::SparseExtent;
+    use crate::rfs::config::{FileConf, LinkConf, Tree};
+
+    fn issue(path: &str, kind: IssueKind) -> ConfigIssue {
+        ConfigIssue {
+            path: PathBuf::from(path),
+            kind,
+        }
+    }
+
+    #[test]
+    fn valid_configuration_should_have_no_issues() {
+        let config = Tree::dir("root")
+            .file("a.txt", "")
+            .dir("sub", |d| {
+                d.file("a.txt", "").symlink("dangling", "missing")
+            })
+            .entry(FileConf {
+                name: String::from("cargo.toml"),
+                content: FileContent::OriginalFile(String::from("Cargo.toml")),
+                ..Default::default()
+            })
+            .clone_dir("src", "src")
+            .entry(FileConf {
+                name: String::from("unordered_sparse.img"),
+                content: FileContent::Sparse {
+                    size: 10,
+                    extents: vec![
+                        SparseExtent {
+                            offset: 7,
+                            content: FileContent::InlineText(String::from("abc")),
+                        },
+                        SparseExtent {
+                            offset: 0,
+                            content: FileContent::InlineText(String::from("abc")),
+                        },
+                    ],
+                },
+                ..Default::default()
+            })
+            .build();
+
+        assert_eq!(config.validate(), vec![]);
+    }
+
+    #[test]
+    fn validation_should_report_all_issues() {
+        let config = Tree::dir("root")
+            .file("", "")
+            .file("../escape.txt", "")
+            .dir("sub", |d| d.file("a.txt", "").file("a.txt", ""))
+            .entry(FileConf {
+                name: String::from("missing.txt"),
+                content: FileContent::OriginalFile(String::from("missing_original.txt")),
+                ..Default::default()
+            })
+            .entry(FileConf {
+                name: String::from("repeat.txt"),
+                content: FileContent::Repeat {
+                    pattern: String::new(),
+                    size: 10,
+                },
+                ..Default::default()
+            })
+            .entry(FileConf {
+                name: String::from("sparse.img"),
+                content: FileContent::Sparse {
+                    size: 10,
+                    extents: vec![
+                        SparseExtent {
+                            offset: 2,
+                            content: FileContent::InlineText(String::from("abc")),
+                        },
+                        SparseExtent {
+                            offset: 4,
+                            content: FileContent::Zeros { size: 6 },
+                        },
+                    ],
+                },
+                ..Default::default()
+            })
+            .entry(LinkConf {
+                name: String::from("link"),
+                target: String::from("missing_target.txt"),
+            })
+            .entry(CloneDirectoryConf {
+                name: String::from("copy"),
+                source: String::from("missing_source"),
+                exclude: vec![String::from("[z-a")],
+                remove: vec![String::from("../outside")],
+                content: Tree::dir("overlay").file("..", "").into_conf().content,
+                ..Default::default()
+            })
+            .build();
+
+        assert_eq!(
+            config.validate(),
+            vec![
+                issue("root/[0]", IssueKind::EmptyName),
+                issue("root/../escape.txt", IssueKind::EscapesSandbox),
+                issue("root/sub/a.txt", IssueKind::DuplicateName),
+                issue(
+                    "root/missing.txt",
+                    IssueKind::MissingOriginalFile(PathBuf::from("missing_original.txt"))
+                ),
+                issue("root/repeat.txt", IssueKind::EmptyPattern),
+                issue("root/sparse.img", IssueKind::InvalidExtent(4)),
+                issue(
+                    "root/link",
+                    IssueKind::MissingLinkTarget(PathBuf::from("missing_target.txt"))
+                ),
+                issue(
+                    "root/copy",
+                    IssueKind::MissingCloneSource(PathBuf::from("missing_source"))
+                ),
+                issue(
+                    "root/copy",
+                    IssueKind::InvalidPattern(
+                        CloneFilter::new(&CloneDirectoryConf {
+                            source: String::from("missing_source"),
+                            exclude: vec![String::from("[z-a")],
+                            ..Default::default()
+                        })
+                        .unwrap_err()
+                        .to_string()
+                    )
+                ),
+                issue(
+                    "root/copy",
+                    IssueKind::InvalidRemovePath(PathBuf::from("../outside"))
+                ),
+                issue("root/copy/..", IssueKind::EscapesSandbox),
+            ]
+        );
+    }
+}
@@ -0,0 +1,428 @@
+//! The `content_generator` module produces the generated, sparse and decompressed file content
+//! in blocks, so large files are written and compared without holding their content in memory.
+use std::fs::File;
+use std::io::{self, Read};
+
+use flate2::read::MultiGzDecoder;
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::config::file_content::{FileContent, SparseExtent};
+
+/// The size of the block produced at once.
+const BLOCK_SIZE: usize = 64 * 1024;
+
+/// Returns the random generator which produces the same numbers for the seed
+/// on every platform and with every version of the crate. The ChaCha8 algorithm is pinned
+/// and the seed is expanded explicitly, the defaults of `rand` may change between its versions.
+pub(crate) fn seeded_rng(seed: u64) -> ChaCha8Rng {
+    let mut key = [0; 32];
+    key[..8].copy_from_slice(&seed.to_le_bytes());
+    ChaCha8Rng::from_seed(key)
+}
+
+/// The source of the generated block.
+enum Source {
+    /// The blocks are filled by the random generator.
+    Random(Box<ChaCha8Rng>),
+
+    /// The same block is repeated, it is empty for the zeros.
+    Repeat,
+}
+
+/// The reader of the generated file content.
+pub(crate) struct ContentGenerator {
+    source: Source,
+    block: Vec<u8>,
+
+    /// The position of the next byte in the block.
+    position: usize,
+
+    /// The number of bytes which are not read yet.
+    remaining: u64,
+}
+
+impl ContentGenerator {
+    /// Creates the reader of the generated content,
+    /// returns None if the content is not generated.
+    pub(crate) fn new(content: &FileContent) -> Option<io::Result<Self>> {
+        match content {
+            FileContent::Random { size, seed } => Some(Ok(Self::random(*size, *seed))),
+            FileContent::Repeat { pattern, size } => Some(Self::repeat(pattern.as_bytes(), *size)),
+            FileContent::Zeros { size } => Some(Ok(Self::zeros(*size))),
+            FileContent::InlineBytes(_)
+            | FileContent::InlineBase64(_)
+            | FileContent::InlineHex(_)
+            | FileContent::InlineEscaped(_)
+            | FileContent::InlineText(_)
+            | FileContent::InlineTemplate(_)
+            | FileContent::OriginalFile(_)
+            | FileContent::Sparse { .. }
+            | FileContent::Gzip { .. }
+            | FileContent::Empty => None,
+        }
+    }
+
+    /// The random bytes, the same seed gives the same content.
+    fn random(size: u64, seed: u64) -> Self {
+        ContentGenerator {
+            source: Source::Random(Box::new(seeded_rng(seed))),
+            block: vec![0; BLOCK_SIZE],
+            // The first block is filled on the first read.
+            position: BLOCK_SIZE,
+            remaining: size,
+        }
+    }
+
+    /// The pattern repeated until the size is reached, the last copy can be cut.
+    fn repeat(pattern: &[u8], size: u64) -> io::Result<Self> {
+        if pattern.is_empty() && size > 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the repeated pattern should not be empty",
+            ));
+        }
+
+        // The block holds the whole copies of the pattern, so the blocks can follow each other.
+        let copies = (BLOCK_SIZE / pattern.len().max(1)).max(1);
+        Ok(ContentGenerator {
+            source: Source::Repeat,
+            block: pattern.repeat(copies),
+            position: 0,
+            remaining: size,
+        })
+    }
+
+    fn zeros(size: u64) -> Self {
+        ContentGenerator {
+            source: Source::Repeat,
+            block: vec![0; BLOCK_SIZE],
+            position: 0,
+            remaining: size,
+        }
+    }
+}
+
+impl Read for ContentGenerator {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+
+        if self.position == self.block.len() {
+            if let Source::Random(rng) = &mut self.source {
+                rng.fill_bytes(&mut self.block);
+            }
+            self.position = 0;
+        }
+
+        let available = (self.block.len() - self.position).min(buf.len());
+        let read = usize::try_from(self.remaining).map_or(available, |rest| available.min(rest));
+        buf[..read].copy_from_slice(&self.block[self.position..self.position + read]);
+        self.position += read;
+        self.remaining -= read as u64;
+
+        Ok(read)
+    }
+}
+
+/// Opens the reader of the extent content.
+pub(crate) type OpenContent = fn(&FileContent) -> io::Result<Box<dyn Read + Send>>;
+
+/// Opens the reader of any file content.
+pub(crate) fn open_content(content: &FileContent) -> io::Result<Box<dyn Read + Send>> {
+    Ok(match content {
+        FileContent::InlineBytes(data)
+        | FileContent::InlineBase64(data)
+        | FileContent::InlineHex(data)
+        | FileContent::InlineEscaped(data) => Box::new(io::Cursor::new(data.clone())),
+        FileContent::InlineText(text) => Box::new(io::Cursor::new(text.clone().into_bytes())),
+        // The templates are replaced with the rendered text before the sandbox is built.
+        FileContent::InlineTemplate(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the template should be rendered before it is read",
+            ))
+        }
+        FileContent::OriginalFile(file_path) => Box::new(File::open(file_path)?),
+        FileContent::Empty => Box::new(io::empty()),
+        FileContent::Sparse { size, extents } => {
+            Box::new(SparseReader::new(*size, extents, open_content))
+        }
+        // The concatenated gzip members are decompressed one after another, like gunzip does.
+        FileContent::Gzip { content } => Box::new(MultiGzDecoder::new(open_content(content)?)),
+        generated => Box::new(
+            ContentGenerator::new(generated)
+                .expect("the other kinds of content should be handled above")?,
+        ),
+    })
+}
+
+/// Returns the error of the extent which overlaps the next extent or exceeds the file size.
+/// The length of some extents, like the decompressed ones, is only known when they are written.
+pub(crate) fn extent_overflow(offset: u64) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "the sparse extent at offset {} overlaps the next extent or exceeds the file size",
+            offset
+        ),
+    )
+}
+
+/// Returns the position where the data of the extent should end: the offset of the next extent
+/// or the file size. The extents are ordered by their offsets.
+pub(crate) fn extent_limit(size: u64, next_extent: Option<&SparseExtent>) -> u64 {
+    next_extent.map_or(size, |extent| extent.offset.min(size))
+}
+
+/// The reader of the sparse file content, the holes are read as zeros.
+pub(crate) struct SparseReader {
+    /// The extents which are not read yet, in the reversed order.
+    extents: Vec<SparseExtent>,
+    open_content: OpenContent,
+
+    /// The reader of the current extent.
+    extent_reader: Option<Box<dyn Read + Send>>,
+
+    /// The offset of the current extent and the position where its data should end.
+    extent_offset: u64,
+    extent_end: u64,
+
+    position: u64,
+    size: u64,
+}
+
+impl SparseReader {
+    pub(crate) fn new(size: u64, extents: &[SparseExtent], open_content: OpenContent) -> Self {
+        let mut extents = extents.to_vec();
+        extents.sort_by_key(|extent| std::cmp::Reverse(extent.offset));
+        SparseReader {
+            extents,
+            open_content,
+            extent_reader: None,
+            extent_offset: 0,
+            extent_end: 0,
+            position: 0,
+            size,
+        }
+    }
+}
+
+impl Read for SparseReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            if let Some(reader) = &mut self.extent_reader {
+                let room = self.extent_end.saturating_sub(self.position);
+                if room == 0 {
+                    // The extent should have no data left at its end.
+                    if reader.read(&mut [0])? > 0 {
+                        return Err(extent_overflow(self.extent_offset));
+                    }
+                } else {
+                    let limit = usize::try_from(room).map_or(buf.len(), |room| buf.len().min(room));
+                    let read = reader.read(&mut buf[..limit])?;
+                    if read > 0 {
+                        self.position += read as u64;
+                        return Ok(read);
+                    }
+                }
+                self.extent_reader = None;
+            }
+
+            let remaining = self.size.saturating_sub(self.position);
+            let limit = usize::try_from(remaining).map_or(buf.len(), |rest| buf.len().min(rest));
+
+            match self.extents.last() {
+                Some(extent) if extent.offset <= self.position => {
+                    let extent = self.extents.pop().expect("the extent should exist");
+                    self.extent_offset = extent.offset;
+                    self.extent_end = extent_limit(self.size, self.extents.last());
+                    self.extent_reader = Some((self.open_content)(&extent.content)?);
+                }
+                // The extents left after the end of the file can not be written.
+                Some(extent) if limit == 0 => return Err(extent_overflow(extent.offset)),
+                None if limit == 0 => return Ok(0),
+                next_extent => {
+                    // The hole up to the next extent or the end of the file.
+                    let hole_end = next_extent.map_or(self.size, |extent| extent.offset);
+                    let hole = usize::try_from(hole_end.min(self.size) - self.position)
+                        .map_or(limit, |hole| hole.min(limit));
+                    buf[..hole].fill(0);
+                    self.position += hole as u64;
+                    return Ok(hole);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(content: FileContent, chunk_size: usize) -> Vec<u8> {
+        let mut generator = ContentGenerator::new(&content)
+            .expect("the content should be generated")
+            .expect("the generator should be created");
+        let mut data = vec![];
+        let mut chunk = vec![0; chunk_size];
+        loop {
+            let read = generator.read(&mut chunk).unwrap();
+            if read == 0 {
+                break;
+            }
+            data.extend_from_slice(&chunk[..read]);
+        }
+        data
+    }
+
+    #[test]
+    fn random_content_should_depend_only_on_seed() {
+        let random = |seed, chunk_size| {
+            generate(
+                FileContent::Random {
+                    size: 200_000,
+                    seed,
+                },
+                chunk_size,
+            )
+        };
+
+        let content = random(7, 4096);
+        assert_eq!(content.len(), 200_000);
+        assert_eq!(content, random(7, 333));
+        assert_ne!(content, random(8, 4096));
+    }
+
+    #[test]
+    fn random_content_should_not_change_between_versions() {
+        let content = generate(
+            FileContent::Random {
+                size: 16,
+                seed: 42,
+            },
+            16,
+        );
+
+        // The first bytes of the ChaCha8 stream with the seed in the key.
+        assert_eq!(
+            content,
+            [135, 168, 143, 25, 113, 52, 39, 89, 43, 247, 157, 22, 164, 138, 35, 73]
+        );
+    }
+
+    #[test]
+    fn repeat_content_should_cut_the_last_copy() {
+        let content = generate(
+            FileContent::Repeat {
+                pattern: String::from("abc"),
+                size: 100_000,
+            },
+            1000,
+        );
+
+        assert_eq!(content.len(), 100_000);
+        assert!(content.chunks(3).all(|copy| b"abc".starts_with(copy)));
+    }
+
+    #[test]
+    fn zeros_content_should_have_the_size() {
+        let content = generate(FileContent::Zeros { size: 70_000 }, 8192);
+
+        assert_eq!(content.len(), 70_000);
+        assert!(content.iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn sparse_content_should_read_holes_as_zeros() {
+        let open_content: OpenContent = |content| {
+            Ok(match content {
+                FileContent::InlineText(text) => {
+                    Box::new(io::Cursor::new(text.clone().into_bytes()))
+                }
+                _ => Box::new(io::empty()),
+            })
+        };
+        let extent = |offset, text: &str| SparseExtent {
+            offset,
+            content: FileContent::InlineText(String::from(text)),
+        };
+        let mut reader = SparseReader::new(12, &[extent(7, "data"), extent(0, "ab")], open_content);
+
+        let mut content = vec![];
+        reader.read_to_end(&mut content).unwrap();
+
+        assert_eq!(content, b"ab\0\0\0\0\0data\0");
+    }
+
+    #[test]
+    fn sparse_content_should_reject_extents_longer_than_their_room() {
+        let open_content: OpenContent = |content| {
+            Ok(match content {
+                FileContent::InlineText(text) => {
+                    Box::new(io::Cursor::new(text.clone().into_bytes()))
+                }
+                _ => Box::new(io::empty()),
+            })
+        };
+        let extent = |offset, text: &str| SparseExtent {
+            offset,
+            content: FileContent::InlineText(String::from(text)),
+        };
+        let read = |size, extents: &[SparseExtent]| {
+            let mut content = vec![];
+            SparseReader::new(size, extents, open_content)
+                .read_to_end(&mut content)
+                .map(|_| content)
+        };
+
+        for (size, extents) in [
+            (10, vec![extent(8, "data")]),
+            (10, vec![extent(0, "data"), extent(2, "ab")]),
+            (10, vec![extent(12, "")]),
+        ] {
+            let error = read(size, &extents).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", error);
+        }
+        assert_eq!(read(6, &[extent(2, "data")]).unwrap(), b"\0\0data");
+    }
+
+    #[test]
+    fn gzip_content_should_be_decompressed() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(b"compressed ").unwrap();
+        let mut compressed = encoder.finish().unwrap();
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(b"members").unwrap();
+        compressed.extend(encoder.finish().unwrap());
+
+        let mut content = String::new();
+        open_content(&FileContent::Gzip {
+            content: Box::new(FileContent::InlineBytes(compressed)),
+        })
+        .unwrap()
+        .read_to_string(&mut content)
+        .unwrap();
+
+        assert_eq!(content, "compressed members");
+    }
+
+    #[test]
+    fn empty_pattern_should_be_rejected() {
+        let generator = ContentGenerator::new(&FileContent::Repeat {
+            pattern: String::new(),
+            size: 1,
+        });
+
+        assert!(generator.is_some_and(|generator| generator.is_err()));
+    }
+}
@@ -0,0 +1,597 @@
+//! The `fs_diff` module compares the content of a sandbox with an expected configuration
+//! and describes the found differences.
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+use std::fs::{self, File, Metadata};
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use super::archive;
+use super::clone_filter::CloneFilter;
+use super::config::clone_directory_conf::{CloneDirectoryConf, CloneFidelity};
+use super::config::ArchiveConf;
+use super::config::ConfigEntry;
+use super::content_generator::open_content;
+use super::fs_tester_error::Result;
+
+const COMPARE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The kind of a file system entry.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EntryKind {
+    Directory,
+    File,
+    Symlink,
+    /// FIFOs, sockets, devices and other special files.
+    Other,
+}
+
+impl EntryKind {
+    fn from_metadata(metadata: &Metadata) -> Self {
+        let file_type = metadata.file_type();
+        if file_type.is_symlink() {
+            EntryKind::Symlink
+        } else if file_type.is_dir() {
+            EntryKind::Directory
+        } else if file_type.is_file() {
+            EntryKind::File
+        } else {
+            EntryKind::Other
+        }
+    }
+
+    fn of_config_entry(entry: &ConfigEntry) -> Self {
+        match entry {
+            ConfigEntry::Directory(_)
+            | ConfigEntry::CloneDirectory(_)
+            | ConfigEntry::Archive(_) => EntryKind::Directory,
+            ConfigEntry::File(_) | ConfigEntry::Link(_) => EntryKind::File,
+            ConfigEntry::Symlink(_) => EntryKind::Symlink,
+        }
+    }
+}
+
+impl Display for EntryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntryKind::Directory => write!(f, "directory"),
+            EntryKind::File => write!(f, "file"),
+            EntryKind::Symlink => write!(f, "symlink"),
+            EntryKind::Other => write!(f, "special file"),
+        }
+    }
+}
+
+/// The difference found for a single entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Difference {
+    /// The expected entry is not found in the sandbox.
+    Missing { kind: EntryKind },
+
+    /// The sandbox contains the entry which is not expected.
+    Extra { kind: EntryKind },
+
+    /// The entry exists but it has another kind, e.g. a directory instead of a file.
+    KindMismatch {
+        expected: EntryKind,
+        found: EntryKind,
+    },
+
+    /// The file content differs from the expected one.
+    /// `offset` is the position of the first different byte.
+    ContentMismatch {
+        expected_len: u64,
+        found_len: u64,
+        offset: u64,
+    },
+
+    /// The symbolic link points to another target.
+    TargetMismatch { expected: PathBuf, found: PathBuf },
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difference::Missing { kind } => write!(f, "missing {}", kind),
+            Difference::Extra { kind } => write!(f, "unexpected {}", kind),
+            Difference::KindMismatch { expected, found } => {
+                write!(f, "expected {} but found {}", expected, found)
+            }
+            Difference::ContentMismatch {
+                expected_len,
+                found_len,
+                offset,
+            } => write!(
+                f,
+                "content differs at byte {} (expected {} bytes, found {} bytes)",
+                offset, expected_len, found_len
+            ),
+            Difference::TargetMismatch { expected, found } => write!(
+                f,
+                "target differs (expected {:?}, found {:?})",
+                expected, found
+            ),
+        }
+    }
+}
+
+/// The difference of an entry with its path relative to the sandbox root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffEntry {
+    pub path: PathBuf,
+    pub difference: Difference,
+}
+
+/// The result of the sandbox comparison with an expected configuration.
+/// It is empty when the sandbox matches the configuration.
+///
+/// The `Display` implementation renders the differences as a tree:
+///
+/// ```text
+/// - missing.txt: missing file
+/// sub/
+///   + extra.txt: unexpected file
+///   ~ test.txt: content differs at byte 0 (expected 4 bytes, found 5 bytes)
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FsDiff {
+    pub entries: Vec<DiffEntry>,
+}
+
+impl FsDiff {
+    /// Returns true if no differences were found.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn push(&mut self, path: PathBuf, difference: Difference) {
+        self.entries.push(DiffEntry { path, difference });
+    }
+
+    /// Compares the directory content with the expected configuration entries.
+    pub(crate) fn compare_config_dir(
+        &mut self,
+        expected: &[ConfigEntry],
+        actual_dir: &Path,
+        relative_dir: &Path,
+    ) -> Result<()> {
+        let mut expected_names = HashSet::new();
+
+        for entry in expected {
+            expected_names.insert(entry.name().to_string());
+            self.compare_config_entry(entry, actual_dir, relative_dir)?;
+        }
+
+        self.find_extra(actual_dir, relative_dir, &expected_names)
+    }
+
+    /// Compares the entry of the directory with the expected configuration entry.
+    fn compare_config_entry(
+        &mut self,
+        entry: &ConfigEntry,
+        actual_dir: &Path,
+        relative_dir: &Path,
+    ) -> Result<()> {
+        let actual_path = actual_dir.join(entry.name());
+        let relative_path = relative_dir.join(entry.name());
+        let expected_kind = EntryKind::of_config_entry(entry);
+
+        if !self.check_kind(&actual_path, &relative_path, expected_kind)? {
+            return Ok(());
+        }
+
+        match entry {
+            ConfigEntry::Directory(conf) => {
+                self.compare_config_dir(&conf.content, &actual_path, &relative_path)
+            }
+            ConfigEntry::CloneDirectory(conf) => {
+                self.compare_clone_dir(conf, &actual_path, &relative_path)
+            }
+            ConfigEntry::File(conf) => {
+                self.compare_content(open_content(&conf.content)?, &actual_path, relative_path)
+            }
+            ConfigEntry::Link(conf) => self.compare_content(
+                Box::new(File::open(&conf.target)?),
+                &actual_path,
+                relative_path,
+            ),
+            ConfigEntry::Symlink(conf) => {
+                self.compare_target(Path::new(&conf.target), &actual_path, relative_path)
+            }
+            ConfigEntry::Archive(conf) => {
+                self.compare_archive_dir(conf, &actual_path, &relative_path)
+            }
+        }
+    }
+
+    /// Compares the extracted archive with the same archive extracted into a temporary directory.
+    fn compare_archive_dir(
+        &mut self,
+        conf: &ArchiveConf,
+        actual_dir: &Path,
+        relative_dir: &Path,
+    ) -> Result<()> {
+        let expected_dir =
+            archive::extract_to_temp_dir(&conf.source, relative_dir.components().count())?;
+        // The extracted symbolic links are expected as they are.
+        let filter = CloneFilter::new(&CloneDirectoryConf {
+            fidelity: CloneFidelity::Exact,
+            ..Default::default()
+        })?;
+
+        self.compare_real_dir(expected_dir.path(), actual_dir, relative_dir, &filter, &[])
+    }
+
+    /// Compares the cloned directory with its source directory,
+    /// taking the removed paths and the overlay content into account.
+    pub(crate) fn compare_clone_dir(
+        &mut self,
+        conf: &CloneDirectoryConf,
+        actual_dir: &Path,
+        relative_dir: &Path,
+    ) -> Result<()> {
+        self.compare_real_dir(
+            Path::new(&conf.source),
+            actual_dir,
+            relative_dir,
+            &CloneFilter::new(conf)?,
+            &conf.content,
+        )
+    }
+
+    /// Compares the directory content with the content of the real source directory.
+    /// Only the entries accepted by the clone filter are expected. The overlay entries
+    /// replace the source entries with the same name, except the directories which are merged.
+    fn compare_real_dir(
+        &mut self,
+        expected_dir: &Path,
+        actual_dir: &Path,
+        relative_dir: &Path,
+        filter: &CloneFilter,
+        overlay: &[ConfigEntry],
+    ) -> Result<()> {
+        let filter = filter.enter_dir(expected_dir)?;
+        let mut expected_names = HashSet::new();
+
+        for entry in fs::read_dir(expected_dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let expected_path = entry.path();
+            let actual_path = actual_dir.join(&name);
+            let relative_path = relative_dir.join(&name);
+            let expected_kind = EntryKind::from_metadata(&fs::symlink_metadata(&expected_path)?);
+
+            let accepted = match expected_kind {
+                EntryKind::Directory => !filter.skips_dir(&expected_path),
+                EntryKind::File => filter.copies_file(&expected_path),
+                EntryKind::Symlink => {
+                    filter.copies_symlinks() && filter.copies_file(&expected_path)
+                }
+                // The special files are never cloned.
+                EntryKind::Other => false,
+            };
+            if !accepted || filter.is_removed(&expected_path) {
+                continue;
+            }
+
+            let overlay_entry = overlay
+                .iter()
+                .find(|entry| entry.name() == name.to_string_lossy());
+            match overlay_entry {
+                Some(ConfigEntry::Directory(conf)) if expected_kind == EntryKind::Directory => {
+                    expected_names.insert(name.to_string_lossy().into_owned());
+                    if self.check_kind(&actual_path, &relative_path, expected_kind)? {
+                        self.compare_real_dir(
+                            &expected_path,
+                            &actual_path,
+                            &relative_path,
+                            &filter,
+                            &conf.content,
+                        )?;
+                    }
+                    continue;
+                }
+                // The replacing entry is compared with the rest of the overlay.
+                Some(_) => continue,
+                None => {}
+            }
+            expected_names.insert(name.to_string_lossy().into_owned());
+
+            // The directories without included content are not created, so only
+            // the included entries inside them are reported as missing.
+            if expected_kind == EntryKind::Directory
+                && filter.has_include()
+                && !filter.includes_dir(&expected_path)
+                && fs::symlink_metadata(&actual_path).is_err()
+            {
+                self.compare_real_dir(&expected_path, &actual_path, &relative_path, &filter, &[])?;
+                continue;
+            }
+
+            if !self.check_kind(&actual_path, &relative_path, expected_kind)? {
+                continue;
+            }
+
+            match expected_kind {
+                EntryKind::Directory => self.compare_real_dir(
+                    &expected_path,
+                    &actual_path,
+                    &relative_path,
+                    &filter,
+                    &[],
+                )?,
+                EntryKind::File => self.compare_content(
+                    Box::new(File::open(&expected_path)?),
+                    &actual_path,
+                    relative_path,
+                )?,
+                EntryKind::Symlink => self.compare_target(
+                    &fs::read_link(&expected_path)?,
+                    &actual_path,
+                    relative_path,
+                )?,
+                EntryKind::Other => {}
+            }
+        }
+
+        for entry in overlay {
+            if expected_names.insert(entry.name().to_string()) {
+                self.compare_config_entry(entry, actual_dir, relative_dir)?;
+            }
+        }
+
+        if fs::symlink_metadata(actual_dir).is_err() {
+            // The pruned directory has no extra entries.
+            return Ok(());
+        }
+
+        self.find_extra(actual_dir, relative_dir, &expected_names)
+    }
+
+    /// Checks the existence and the kind of the actual entry.
+    /// Returns true if the entry can be compared further.
+    fn check_kind(
+        &mut self,
+        actual_path: &Path,
+        relative_path: &Path,
+        expected: EntryKind,
+    ) -> Result<bool> {
+        let metadata = match fs::symlink_metadata(actual_path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                self.push(
+                    relative_path.to_path_buf(),
+                    Difference::Missing { kind: expected },
+                );
+                return Ok(false);
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        let found = EntryKind::from_metadata(&metadata);
+        if found != expected {
+            self.push(
+                relative_path.to_path_buf(),
+                Difference::KindMismatch { expected, found },
+            );
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    fn find_extra(
+        &mut self,
+        actual_dir: &Path,
+        relative_dir: &Path,
+        expected_names: &HashSet<String>,
+    ) -> Result<()> {
+        let mut extra = vec![];
+        for entry in fs::read_dir(actual_dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            if !expected_names.contains(name.to_string_lossy().as_ref()) {
+                let kind = EntryKind::from_metadata(&fs::symlink_metadata(entry.path())?);
+                extra.push((relative_dir.join(name), kind));
+            }
+        }
+
+        extra.sort_by(|left, right| left.0.cmp(&right.0));
+        for (path, kind) in extra {
+            self.push(path, Difference::Extra { kind });
+        }
+
+        Ok(())
+    }
+
+    fn compare_content(
+        &mut self,
+        expected: Box<dyn Read>,
+        actual_path: &Path,
+        relative_path: PathBuf,
+    ) -> Result<()> {
+        let mut expected = BufReader::with_capacity(COMPARE_CHUNK_SIZE, expected);
+        let mut actual = BufReader::with_capacity(COMPARE_CHUNK_SIZE, File::open(actual_path)?);
+        let mut expected_chunk = vec![0; COMPARE_CHUNK_SIZE];
+        let mut actual_chunk = vec![0; COMPARE_CHUNK_SIZE];
+        let mut chunk_start: u64 = 0;
+
+        loop {
+            let expected_read = Self::read_chunk(&mut expected, &mut expected_chunk)?;
+            let actual_read = Self::read_chunk(&mut actual, &mut actual_chunk)?;
+
+            let same_len = expected_read.min(actual_read);
+            let position = expected_chunk[..same_len]
+                .iter()
+                .zip(&actual_chunk[..same_len])
+                .position(|(left, right)| left != right);
+
+            match position {
+                None if expected_read == actual_read => {
+                    if expected_read == 0 {
+                        return Ok(());
+                    }
+                    chunk_start += expected_read as u64;
+                }
+                _ => {
+                    // The content differs, so the rest is only counted to report the lengths.
+                    let offset = chunk_start + position.unwrap_or(same_len) as u64;
+                    let expected_len = chunk_start
+                        + expected_read as u64
+                        + io::copy(&mut expected, &mut io::sink())?;
+                    let found_len =
+                        chunk_start + actual_read as u64 + io::copy(&mut actual, &mut io::sink())?;
+
+                    self.push(
+                        relative_path,
+                        Difference::ContentMismatch {
+                            expected_len,
+                            found_len,
+                            offset,
+                        },
+                    );
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    /// Fills the buffer as much as possible, so the chunks of both readers are aligned.
+    fn read_chunk(reader: &mut impl Read, buffer: &mut [u8]) -> io::Result<usize> {
+        let mut filled = 0;
+        while filled < buffer.len() {
+            match reader.read(&mut buffer[filled..]) {
+                Ok(0) => break,
+                Ok(read) => filled += read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(filled)
+    }
+
+    fn compare_target(
+        &mut self,
+        expected: &Path,
+        actual_path: &Path,
+        relative_path: PathBuf,
+    ) -> Result<()> {
+        let found = fs::read_link(actual_path)?;
+        if found != expected {
+            self.push(
+                relative_path,
+                Difference::TargetMismatch {
+                    expected: expected.to_path_buf(),
+                    found,
+                },
+            );
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for FsDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut entries: Vec<&DiffEntry> = self.entries.iter().collect();
+        entries.sort_by(|left, right| left.path.cmp(&right.path));
+
+        let mut printed_dirs: HashSet<PathBuf> = HashSet::new();
+        for entry in entries {
+            let components: Vec<_> = entry.path.components().collect();
+            let depth = components.len().saturating_sub(1);
+
+            // Print the parent directories once, so the differences look like a tree.
+            let mut dir = PathBuf::new();
+            for (level, component) in components.iter().take(depth).enumerate() {
+                dir.push(component);
+                if printed_dirs.insert(dir.clone()) {
+                    writeln!(
+                        f,
+                        "{}{}/",
+                        "  ".repeat(level),
+                        component.as_os_str().to_string_lossy()
+                    )?;
+                }
+            }
+
+            let marker = match entry.difference {
+                Difference::Missing { .. } => '-',
+                Difference::Extra { .. } => '+',
+                _ => '~',
+            };
+            let name = entry
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_default();
+            writeln!(
+                f,
+                "{}{} {}: {}",
+                "  ".repeat(depth),
+                marker,
+                name,
+                entry.difference
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_renders_tree_of_differences() {
+        let diff = FsDiff {
+            entries: vec![
+                DiffEntry {
+                    path: PathBuf::from("sub/test.txt"),
+                    difference: Difference::ContentMismatch {
+                        expected_len: 4,
+                        found_len: 5,
+                        offset: 0,
+                    },
+                },
+                DiffEntry {
+                    path: PathBuf::from("missing.txt"),
+                    difference: Difference::Missing {
+                        kind: EntryKind::File,
+                    },
+                },
+                DiffEntry {
+                    path: PathBuf::from("sub/extra"),
+                    difference: Difference::Extra {
+                        kind: EntryKind::Directory,
+                    },
+                },
+            ],
+        };
+
+        assert_eq!(
+            format!("{}", diff),
+            "- missing.txt: missing file\n\
+             sub/\n  \
+               + extra: unexpected directory\n  \
+               ~ test.txt: content differs at byte 0 (expected 4 bytes, found 5 bytes)\n"
+        );
+    }
+
+    #[test]
+    fn test_display_for_empty_diff() {
+        assert_eq!(format!("{}", FsDiff::default()), "");
+    }
+
+    #[test]
+    fn test_read_chunk_fills_buffer() {
+        let mut reader = io::Cursor::new(vec![1u8; 10]).chain(io::Cursor::new(vec![2u8; 10]));
+        let mut buffer = [0u8; 15];
+
+        assert_eq!(FsDiff::read_chunk(&mut reader, &mut buffer).unwrap(), 15);
+        assert_eq!(FsDiff::read_chunk(&mut reader, &mut buffer).unwrap(), 5);
+        assert_eq!(FsDiff::read_chunk(&mut reader, &mut buffer).unwrap(), 0);
+    }
+}
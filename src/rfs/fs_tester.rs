@@ -1,31 +1,59 @@
 use futures::future::BoxFuture;
 use futures::FutureExt;
-use rand::Rng;
-use std::env;
-use std::sync::Arc;
+use std::fs::FileTimes;
+use std::io::{Read, SeekFrom};
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 use std::{
     io::{self},
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
 };
 use tokio::fs::{self, hard_link, File};
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
 use tokio::sync::Semaphore;
 use walkdir::WalkDir;
 
+use crate::rfs::archive;
+use crate::rfs::clone_filter::CloneFilter;
+use crate::rfs::content_generator::{extent_limit, extent_overflow, open_content};
+use crate::rfs::fs_diff::FsDiff;
 use crate::rfs::fs_tester_error::{FsTesterError, Result};
+use crate::rfs::fs_tester_options::{default_base_dir, env_flag, FsTesterOptions};
+use crate::rfs::reaper;
+use crate::rfs::sparse;
+use crate::rfs::template::TemplateContext;
+use serde::Serialize;
 
 use super::config::clone_directory_conf::CloneDirectoryConf;
 use super::config::config_entry::ConfigEntry;
 use super::config::configuration::Configuration;
 use super::config::directory_conf::DirectoryConf;
 use super::config::file_content::FileContent;
-use super::config::{FileConf, LinkConf};
+use super::config::symlink_conf;
+use super::config::{ArchiveConf, FileConf, LinkConf, SymlinkConf};
 
 const LINKS_ALLOWED_VAR_NAME: &str = "LINKS_ALLOWED";
 const SEMAPHORE_LIMIT: usize = 100;
 
-struct Permissions {
+/// The size of the chunks which the generated and decompressed file content is written in.
+const GENERATED_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// The settings shared by all tasks building the sandbox.
+struct BuildSettings {
     links_allowed: bool,
+
+    /// Reports every failed entry instead of cancelling the build on the first failure.
+    collect_errors: bool,
+}
+
+/// The source metadata of the cloned directory, whose times and permissions
+/// are copied after the clone is complete.
+struct ClonedDirAttributes {
+    path: PathBuf,
+    metadata: std::fs::Metadata,
 }
 
 /// File System Tester is used to create a configured structure in a directory
@@ -61,20 +89,65 @@ struct Permissions {
 ///     Ok(())
 /// }
 /// ```
+///
+/// # Example of use in async tests
+///
+/// ```rust
+/// use rfs_tester::{FsTester, FsTesterError};
+///
+/// #[tokio::test]
+/// async fn test_async_file_creation() -> Result<(), FsTesterError> {
+///     let config_str = r#"---
+///     - !directory
+///         name: test_doc_test_for_async_fs_tester
+///         content:
+///           - !file
+///               name: test.txt
+///               content:
+///                 !inline_text "Hello, world!"
+///     "#;
+///
+///     let tester = FsTester::new_async(config_str, ".").await?;
+///
+///     tester
+///         .perform_fs_test_async(async |dirname| {
+///             let file_path = std::path::PathBuf::from(dirname).join("test.txt");
+///             let content = tokio::fs::read_to_string(file_path).await?;
+///             assert_eq!(content, "Hello, world!");
+///             Ok(())
+///         })
+///         .await;
+///     Ok(())
+/// }
+/// ```
+// The examples show the test functions as they are written in the user's crate.
+#[allow(clippy::test_attr_in_doctest)]
 pub struct FsTester {
     pub config: Configuration,
     pub base_dir: String,
+
+    /// The sandbox directory path, the same as `base_dir`.
+    root: PathBuf,
+    options: FsTesterOptions,
+
+    /// The reason of the test failure, if the test closure returned an error or panicked.
+    failure: Mutex<Option<String>>,
 }
 
-impl FsTester {
-    fn get_random_code() -> u64 {
-        rand::rng().random::<u64>()
-    }
+/// The description of the sandbox retained after the test failure.
+#[derive(Serialize)]
+struct Manifest<'a> {
+    sandbox: &'a str,
+    failure: &'a str,
+    config: &'a Configuration,
+}
 
-    fn gen_dir_path(dir_path: &PathBuf, name: &str, level: u32) -> PathBuf {
+impl FsTester {
+    /// The sandbox directory (level 0) is created by the naming strategy beforehand,
+    /// so its path is passed as `dir_path`.
+    fn gen_dir_path(dir_path: &Path, name: &str, level: u32) -> PathBuf {
         if level == 0 {
-            let uniq_code = Self::get_random_code();
-            dir_path.join(format!("{}_{}", name, uniq_code))
+            dir_path.to_path_buf()
         } else {
             dir_path.join(name)
         }
@@ -97,19 +170,165 @@ impl FsTester {
         }
     }
 
+    /// Takes the access and modification times of the source entry.
+    fn file_times(metadata: &std::fs::Metadata) -> io::Result<FileTimes> {
+        Ok(FileTimes::new()
+            .set_accessed(metadata.accessed()?)
+            .set_modified(metadata.modified()?))
+    }
+
     async fn create_dir(dirname: Arc<PathBuf>) -> Result<String> {
         fs::create_dir_all(dirname.as_ref()).await?;
 
         Ok(dirname.to_string_lossy().into_owned())
     }
 
+    /// Applies the configured permission bits to the created entry.
+    /// On non-unix platforms only the read-only flag can be derived from the mode.
+    fn set_mode(path: &Path, mode: Option<u32>) -> Result<()> {
+        if let Some(mode) = mode {
+            #[cfg(unix)]
+            let permissions = std::fs::Permissions::from_mode(mode);
+
+            #[cfg(not(unix))]
+            let permissions = {
+                let mut permissions = std::fs::metadata(path)?.permissions();
+                permissions.set_readonly(mode & 0o222 == 0);
+                permissions
+            };
+
+            std::fs::set_permissions(path, permissions)?;
+        }
+
+        Ok(())
+    }
+
+    /// Opens the file or directory to change its timestamps.
+    fn open_for_times(path: &Path) -> io::Result<std::fs::File> {
+        #[cfg(windows)]
+        {
+            use std::os::windows::fs::OpenOptionsExt;
+            // FILE_FLAG_BACKUP_SEMANTICS is required to open directories
+            std::fs::OpenOptions::new()
+                .write(true)
+                .custom_flags(0x02000000)
+                .open(path)
+        }
+
+        #[cfg(not(windows))]
+        std::fs::File::open(path)
+    }
+
+    /// Sets the access and modification times of the entry, the missing ones are left untouched.
+    fn set_times(
+        path: &Path,
+        accessed: Option<SystemTime>,
+        modified: Option<SystemTime>,
+    ) -> io::Result<()> {
+        if accessed.is_none() && modified.is_none() {
+            return Ok(());
+        }
+
+        let mut times = FileTimes::new();
+        if let Some(accessed) = accessed {
+            times = times.set_accessed(accessed);
+        }
+        if let Some(modified) = modified {
+            times = times.set_modified(modified);
+        }
+
+        Self::open_for_times(path)?.set_times(times)
+    }
+
+    /// Applies timestamps and permission bits after the whole sandbox is built.
+    /// The children are handled before their parent directory, so creating an entry
+    /// does not clobber the directory modification time, and a locked-down directory
+    /// does not prevent access to its content.
+    fn apply_attributes(entry: &ConfigEntry, path: &Path, now: SystemTime) -> Result<()> {
+        match entry {
+            ConfigEntry::Directory(conf) => {
+                for child in &conf.content {
+                    Self::apply_attributes(child, &path.join(child.name()), now)
+                        .map_err(|err| err.within_entry(child))?;
+                }
+                Self::set_times(
+                    path,
+                    conf.accessed.map(|time| time.resolve(now)),
+                    conf.modified.map(|time| time.resolve(now)),
+                )?;
+                Self::set_mode(path, conf.mode)
+            }
+            ConfigEntry::File(conf) => {
+                Self::set_times(
+                    path,
+                    conf.accessed.map(|time| time.resolve(now)),
+                    conf.modified.map(|time| time.resolve(now)),
+                )?;
+                Self::set_mode(path, conf.mode)
+            }
+            ConfigEntry::CloneDirectory(conf) => {
+                for child in &conf.content {
+                    Self::apply_attributes(child, &path.join(child.name()), now)
+                        .map_err(|err| err.within_entry(child))?;
+                }
+                Self::set_mode(path, conf.mode)
+            }
+            ConfigEntry::Link(_) | ConfigEntry::Symlink(_) | ConfigEntry::Archive(_) => Ok(()),
+        }
+    }
+
+    /// Gives the owner full access to the directory and all nested directories,
+    /// so the sandbox can be removed even if it contains locked-down entries.
+    fn restore_write_permissions(path: &Path) -> io::Result<()> {
+        let metadata = std::fs::symlink_metadata(path)?;
+        if metadata.is_dir() {
+            let mut permissions = metadata.permissions();
+
+            #[cfg(unix)]
+            permissions.set_mode(permissions.mode() | 0o700);
+
+            #[cfg(not(unix))]
+            #[allow(clippy::permissions_set_readonly_false)]
+            permissions.set_readonly(false);
+
+            std::fs::set_permissions(path, permissions)?;
+
+            for entry in std::fs::read_dir(path)? {
+                Self::restore_write_permissions(&entry?.path())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes the sandbox directory. If the removal fails, for example because of
+    /// read-only directories configured with a mode, the write permissions are restored
+    /// and the removal is repeated.
+    pub(crate) fn remove_sandbox(path: &Path) -> io::Result<()> {
+        if std::fs::remove_dir_all(path).is_err() {
+            Self::restore_write_permissions(path)?;
+            std::fs::remove_dir_all(path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Copies the source directory content. The directory times and permissions are not copied
+    /// here, because the removal and the overlay content change the directories afterwards
+    /// or can be prohibited by the copied permissions. The metadata of the source directories
+    /// which keep their times or permissions is returned instead, the nested ones first.
     async fn copy_dir(
+        conf: Arc<CloneDirectoryConf>,
+        filter: CloneFilter,
         src_path: Arc<PathBuf>,
         dst_path: Arc<PathBuf>,
-        permissions: Arc<Permissions>,
+        level: u32,
+        settings: Arc<BuildSettings>,
         semaphore: Arc<Semaphore>,
-    ) -> Result<String> {
-        let dst_dir_name = Self::create_dir(dst_path.clone()).await?;
+    ) -> Result<Vec<ClonedDirAttributes>> {
+        Self::create_dir(dst_path.clone()).await?;
+        let mut dir_attributes = vec![];
+        let filter = filter.enter_dir(&src_path)?;
         // Reading source dir
         let src_dir_entries_iter = WalkDir::new(src_path.clone().as_ref())
             .max_depth(1)
@@ -117,48 +336,170 @@ impl FsTester {
             .skip(1); // skip self directory
         let mut handles = vec![];
 
-        for entry in src_dir_entries_iter {
-            let semaphore = semaphore.clone();
-            let entry = entry?;
-            let src_entry_path = Arc::new(PathBuf::from(entry.path()));
-            let filename = src_entry_path
-                .into_iter()
-                .last()
-                .expect("source dir should not be empty");
-            let dst_entry_path = Arc::new(dst_path.clone().join(filename));
-            let entry_metadata = entry.clone().metadata()?;
-
-            if entry_metadata.is_file() {
-                // copy file
-                let mut src_file = File::open(src_entry_path.clone().as_ref()).await?;
-                let mut dst_file = File::create(dst_entry_path.clone().as_ref()).await?;
-                let handle = tokio::spawn(async move {
-                    let _permit = semaphore
-                        .acquire()
-                        .await
-                        .expect("It seems that the semaphore has been closed.");
-                    tokio::io::copy(&mut src_file, &mut dst_file).await
-                });
-
-                handles.push(handle);
-            } else if entry_metadata.is_dir() {
-                // start recursion for child dir
-                let src_entry_path = src_entry_path.clone();
-                Self::copy_dir_boxed(
-                    src_entry_path.clone(),
-                    dst_entry_path,
-                    permissions.clone(),
-                    semaphore.clone(),
-                )
-                .await?;
+        // The file copies already spawned are awaited even if the walk fails.
+        let walked: Result<()> = async {
+            for entry in src_dir_entries_iter {
+                // The build was cancelled by the failure of another entry.
+                if semaphore.is_closed() {
+                    return Err(FsTesterError::cancelled());
+                }
+
+                let semaphore = semaphore.clone();
+                let entry = entry?;
+                let src_entry_path = Arc::new(PathBuf::from(entry.path()));
+                let filename = src_entry_path
+                    .file_name()
+                    .expect("source dir should not be empty");
+                let dst_entry_path = Arc::new(dst_path.clone().join(filename));
+                // The symbolic links are not followed, so this is the metadata of the link itself.
+                let entry_metadata = entry.clone().metadata()?;
+
+                if entry_metadata.is_symlink() {
+                    if !filter.copies_symlinks() || !filter.copies_file(&src_entry_path) {
+                        continue;
+                    }
+
+                    let target_name = fs::read_link(src_entry_path.as_ref()).await?;
+                    if !conf.allow_outside
+                        && symlink_conf::target_escapes(&target_name, level as usize)
+                    {
+                        return Err(FsTesterError::path_escapes_sandbox(
+                            &src_path.join(&target_name),
+                        ));
+                    }
+                    let target_is_dir = src_entry_path.is_dir();
+                    Self::symlink(&target_name, &dst_entry_path, target_is_dir).await?;
+                } else if entry_metadata.is_file() {
+                    if !filter.copies_file(&src_entry_path) {
+                        continue;
+                    }
+
+                    // copy file
+                    let mut src_file = File::open(src_entry_path.clone().as_ref()).await?;
+                    let mut dst_file = File::create(dst_entry_path.clone().as_ref()).await?;
+                    let preserve_timestamps = conf.preserves_timestamps();
+                    let preserve_permissions = conf.preserves_permissions();
+                    let preserve_holes = conf.preserve_holes;
+                    let handle = tokio::spawn(async move {
+                        let _permit = semaphore.acquire().await?;
+                        let dst_file = if preserve_holes {
+                            let src_file = src_file.into_std().await;
+                            let dst_file = dst_file.into_std().await;
+                            tokio::task::spawn_blocking(move || {
+                                sparse::copy_sparse(&src_file, &dst_file).map(|_| dst_file)
+                            })
+                            .await??
+                        } else {
+                            tokio::io::copy(&mut src_file, &mut dst_file).await?;
+                            dst_file.into_std().await
+                        };
+
+                        if preserve_timestamps {
+                            dst_file.set_times(Self::file_times(&entry_metadata)?)?;
+                        }
+                        if preserve_permissions {
+                            dst_file.set_permissions(entry_metadata.permissions())?;
+                        }
+                        Result::Ok(())
+                    });
+
+                    handles.push(handle);
+                } else if entry_metadata.is_dir() {
+                    if filter.skips_dir(&src_entry_path) {
+                        continue;
+                    }
+
+                    // start recursion for child dir
+                    let src_entry_path = src_entry_path.clone();
+                    let child_attributes = Self::copy_dir_boxed(
+                        conf.clone(),
+                        filter.clone(),
+                        src_entry_path.clone(),
+                        dst_entry_path.clone(),
+                        level + 1,
+                        settings.clone(),
+                        semaphore.clone(),
+                    )
+                    .await?;
+
+                    // Directories without included content are not a part of the clone.
+                    if filter.has_include()
+                        && !filter.includes_dir(&src_entry_path)
+                        && fs::read_dir(dst_entry_path.as_ref())
+                            .await?
+                            .next_entry()
+                            .await?
+                            .is_none()
+                    {
+                        fs::remove_dir(dst_entry_path.as_ref()).await?;
+                    } else {
+                        dir_attributes.extend(child_attributes);
+                    }
+                } else if filter.rejects_special_file(&src_entry_path) {
+                    return Err(FsTesterError::special_file(&src_entry_path));
+                }
             }
+
+            Ok(())
         }
+        .await;
 
+        let mut errors = vec![];
         for handle in handles {
-            handle.await??;
+            if let Err(err) = handle
+                .await
+                .map_err(FsTesterError::from)
+                .and_then(|copied| copied)
+            {
+                errors.push(err);
+            }
+        }
+        walked?;
+        Self::combine_errors(errors, false)?;
+
+        if conf.preserves_timestamps() || conf.preserves_permissions() {
+            dir_attributes.push(ClonedDirAttributes {
+                path: dst_path.to_path_buf(),
+                metadata: fs::metadata(src_path.as_ref()).await?,
+            });
+        }
+
+        Ok(dir_attributes)
+    }
+
+    /// Copies the times and permissions of the source directories to the cloned ones
+    /// after the clone is complete. The nested directories come first, so a read-only
+    /// directory does not prevent setting the attributes of its content.
+    /// The directories deleted by the removal list are skipped.
+    fn apply_cloned_dir_attributes(
+        conf: &CloneDirectoryConf,
+        dir_attributes: &[ClonedDirAttributes],
+        dir_path: &Path,
+    ) -> Result<()> {
+        let removed_paths: Vec<PathBuf> = conf
+            .remove
+            .iter()
+            .map(|relative_path| dir_path.join(relative_path))
+            .collect();
+
+        for attributes in dir_attributes {
+            if removed_paths
+                .iter()
+                .any(|removed_path| attributes.path.starts_with(removed_path))
+            {
+                continue;
+            }
+
+            if conf.preserves_timestamps() {
+                Self::open_for_times(&attributes.path)?
+                    .set_times(Self::file_times(&attributes.metadata)?)?;
+            }
+            if conf.preserves_permissions() {
+                std::fs::set_permissions(&attributes.path, attributes.metadata.permissions())?;
+            }
         }
 
-        Ok(dst_dir_name)
+        Ok(())
     }
 
     async fn create_file(conf: Arc<FileConf>, dir_path: Arc<PathBuf>) -> Result<String> {
@@ -166,29 +507,108 @@ impl FsTester {
         let mut dst_file = File::create(&dst_file_name).await?;
 
         match &conf.content {
-            FileContent::InlineBytes(data) => {
-                dst_file.write_all(&data).await?;
+            // The gaps between the extents are skipped, so they are left as holes.
+            // The extents of unknown length are checked after they are written.
+            FileContent::Sparse { size, extents } => {
+                let mut extents = extents.iter().collect::<Vec<_>>();
+                extents.sort_by_key(|extent| extent.offset);
+                for (index, extent) in extents.iter().enumerate() {
+                    dst_file.seek(SeekFrom::Start(extent.offset)).await?;
+                    Self::write_content(&mut dst_file, &extent.content).await?;
+                    let limit = extent_limit(*size, extents.get(index + 1).copied());
+                    if extent.offset > *size || dst_file.stream_position().await? > limit {
+                        return Err(extent_overflow(extent.offset).into());
+                    }
+                }
+                dst_file.set_len(*size).await?;
+            }
+            content => Self::write_content(&mut dst_file, content).await?,
+        }
+        dst_file.flush().await?;
+
+        Ok(dst_file_name.to_string_lossy().into_owned())
+    }
+
+    /// Writes the content at the current position of the file.
+    async fn write_content(dst_file: &mut File, content: &FileContent) -> Result<()> {
+        match content {
+            FileContent::InlineBytes(data)
+            | FileContent::InlineBase64(data)
+            | FileContent::InlineHex(data)
+            | FileContent::InlineEscaped(data) => {
+                dst_file.write_all(data).await?;
             }
             FileContent::InlineText(text) => {
                 dst_file.write_all(text.as_bytes()).await?;
             }
             FileContent::OriginalFile(file_path) => {
                 let mut src_file = File::open(file_path).await?;
-                tokio::io::copy(&mut src_file, &mut dst_file).await?;
+                tokio::io::copy(&mut src_file, dst_file).await?;
             }
             FileContent::Empty => {}
+            FileContent::Sparse { .. } => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "the sparse content can not be nested",
+                )
+                .into());
+            }
+            // The generated and decompressed content, including the decompressed original
+            // file, is read on the blocking thread pool.
+            content => {
+                Self::write_blocking_content(dst_file, content.clone()).await?;
+            }
         }
 
-        Ok(dst_file_name.to_string_lossy().into_owned())
+        Ok(())
+    }
+
+    /// Writes the content, which is read by a blocking reader, so it is produced chunk by chunk
+    /// on the blocking thread pool and the runtime workers only write the chunks.
+    async fn write_blocking_content(dst_file: &mut File, content: FileContent) -> Result<()> {
+        let (sender, mut receiver) = tokio::sync::mpsc::channel::<io::Result<Vec<u8>>>(2);
+        let producer = tokio::task::spawn_blocking(move || {
+            let mut reader = match open_content(&content) {
+                Ok(reader) => reader,
+                Err(err) => {
+                    let _ = sender.blocking_send(Err(err));
+                    return;
+                }
+            };
+            loop {
+                let mut chunk = vec![0; GENERATED_CHUNK_SIZE];
+                let chunk = match reader.read(&mut chunk) {
+                    Ok(0) => return,
+                    Ok(read) => {
+                        chunk.truncate(read);
+                        Ok(chunk)
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => Err(err),
+                };
+                let is_err = chunk.is_err();
+                // The receiver is gone when the writing has failed.
+                if sender.blocking_send(chunk).is_err() || is_err {
+                    return;
+                }
+            }
+        });
+
+        while let Some(chunk) = receiver.recv().await {
+            dst_file.write_all(&chunk?).await?;
+        }
+        producer.await?;
+
+        Ok(())
     }
 
     /// WARNING!!! Use links with caution, as making changes to the content using a link may modify the original file.
     async fn create_link(
         conf: Arc<LinkConf>,
         dir_path: Arc<PathBuf>,
-        permissions: Arc<Permissions>,
+        settings: Arc<BuildSettings>,
     ) -> Result<String> {
-        if permissions.links_allowed {
+        if settings.links_allowed {
             let link_name = dir_path.join(&conf.name);
             let target_name = PathBuf::from(&conf.target);
             hard_link(target_name, &link_name).await?;
@@ -199,11 +619,59 @@ impl FsTester {
         }
     }
 
+    /// Symbolic links do not share data with their targets, so they are always allowed.
+    /// The target is stored as is, so it can be relative, absolute, dangling or looping.
+    async fn create_symlink(conf: Arc<SymlinkConf>, dir_path: Arc<PathBuf>) -> Result<String> {
+        let link_name = dir_path.join(&conf.name);
+        let target_name = PathBuf::from(&conf.target);
+        let target_is_dir = dir_path.join(&target_name).is_dir();
+        Self::symlink(&target_name, &link_name, target_is_dir).await?;
+
+        Ok(link_name.to_string_lossy().into_owned())
+    }
+
+    /// Creates the symbolic link. Windows distinguishes the links to directories
+    /// from the links to files, other platforms ignore `target_is_dir`.
+    #[cfg_attr(not(windows), allow(unused_variables))]
+    async fn symlink(target_name: &Path, link_name: &Path, target_is_dir: bool) -> io::Result<()> {
+        #[cfg(unix)]
+        fs::symlink(target_name, link_name).await?;
+
+        #[cfg(windows)]
+        if target_is_dir {
+            fs::symlink_dir(target_name, link_name).await?;
+        } else {
+            fs::symlink_file(target_name, link_name).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Extracts the archive into the new directory on the blocking thread pool,
+    /// because the tar and zip readers are synchronous. The directory is merged with
+    /// the cloned directory of the same name, like the configured directories.
+    async fn extract_archive(
+        conf: Arc<ArchiveConf>,
+        dir_path: Arc<PathBuf>,
+        level: u32,
+    ) -> Result<String> {
+        let dst_dir_path = dir_path.join(&conf.name);
+        fs::create_dir_all(&dst_dir_path).await?;
+
+        let extracted_path = dst_dir_path.clone();
+        tokio::task::spawn_blocking(move || {
+            archive::extract(&conf.source, &extracted_path, level as usize)
+        })
+        .await??;
+
+        Ok(dst_dir_path.to_string_lossy().into_owned())
+    }
+
     async fn clone_directory(
         conf: Arc<CloneDirectoryConf>,
         parent_path: Arc<PathBuf>,
         level: u32,
-        permissions: Arc<Permissions>,
+        settings: Arc<BuildSettings>,
         semaphore: Arc<Semaphore>,
     ) -> Result<String> {
         let dst_dir_path = Arc::new(Self::gen_dir_path(
@@ -212,13 +680,32 @@ impl FsTester {
             level,
         ));
         let src_dir_path = Arc::new(PathBuf::from(&conf.source));
-
-        Self::copy_dir(
-            src_dir_path.clone(),
-            dst_dir_path.clone(),
-            permissions.clone(),
-            semaphore.clone(),
-        )
+        let filter = CloneFilter::new(&conf)?;
+
+        async {
+            let dir_attributes = Self::copy_dir(
+                conf.clone(),
+                filter,
+                src_dir_path.clone(),
+                dst_dir_path.clone(),
+                level,
+                settings.clone(),
+                semaphore.clone(),
+            )
+            .await?;
+
+            Self::remove_cloned_paths(&conf.remove, &dst_dir_path)?;
+            Self::clear_overlay_paths(&conf.content, &dst_dir_path)?;
+            Self::build_content_boxed(
+                &conf.content,
+                dst_dir_path.clone(),
+                level,
+                settings.clone(),
+                semaphore.clone(),
+            )
+            .await?;
+            Self::apply_cloned_dir_attributes(&conf, &dir_attributes, &dst_dir_path)
+        }
         .await
         .map_err(|mut err| {
             if level == 0 {
@@ -230,11 +717,61 @@ impl FsTester {
         Ok(dst_dir_path.to_string_lossy().into_owned())
     }
 
+    /// Deletes the listed paths from the cloned directory.
+    fn remove_cloned_paths(paths: &[String], dir_path: &Path) -> Result<()> {
+        for relative_path in paths {
+            let relative_path = Path::new(relative_path);
+            if !relative_path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+            {
+                return Err(FsTesterError::path_escapes_sandbox(relative_path));
+            }
+
+            // The cloned symbolic links on the way should not lead the removal outside.
+            let path = dir_path.join(relative_path);
+            let parent = path.parent().unwrap_or(dir_path);
+            if !std::fs::canonicalize(parent)?.starts_with(std::fs::canonicalize(dir_path)?) {
+                return Err(FsTesterError::path_escapes_sandbox(relative_path));
+            }
+
+            if std::fs::symlink_metadata(&path)?.is_dir() {
+                std::fs::remove_dir_all(&path)?;
+            } else {
+                std::fs::remove_file(&path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Removes the cloned files and links which are replaced by the overlay entries,
+    /// so the new entries are not written through the cloned symbolic links.
+    /// The cloned directories are kept to merge the overlay directories with them.
+    fn clear_overlay_paths(content: &[ConfigEntry], dir_path: &Path) -> Result<()> {
+        for entry in content {
+            let path = dir_path.join(entry.name());
+            let Ok(metadata) = std::fs::symlink_metadata(&path) else {
+                continue;
+            };
+
+            match entry {
+                ConfigEntry::Directory(conf) if metadata.is_dir() => {
+                    Self::clear_overlay_paths(&conf.content, &path)?
+                }
+                _ if !metadata.is_dir() => std::fs::remove_file(&path)?,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
     async fn build_directory_with_content(
         directory_conf: Arc<DirectoryConf>,
         parent_path: Arc<PathBuf>,
         level: u32,
-        permissions: Arc<Permissions>,
+        settings: Arc<BuildSettings>,
         semaphore: Arc<Semaphore>,
     ) -> Result<String> {
         let directory_conf = directory_conf.clone();
@@ -246,99 +783,172 @@ impl FsTester {
 
         Self::create_dir(dst_dir_path.clone()).await?;
 
-        let mut handles = vec![];
-
-        for entry in &directory_conf.content {
-            let entry = entry.clone();
-            let semaphore = semaphore.clone();
-            let permissions = permissions.clone();
-            let dst_dir_path = dst_dir_path.clone();
+        Self::build_content(
+            &directory_conf.content,
+            dst_dir_path.clone(),
+            level,
+            settings,
+            semaphore,
+        )
+        .await
+        .map_err(|mut err| {
+            if level == 0 {
+                err.set_sandbox_dir(Some(String::from(dst_dir_path.to_string_lossy())));
+            }
+            err
+        })?;
 
-            match entry {
-                ConfigEntry::Directory(conf) => {
-                    let conf = Arc::new(conf);
+        Ok(dst_dir_path.to_string_lossy().into_owned())
+    }
 
-                    let handle = tokio::spawn(async move {
-                        Self::build_directory_with_content_boxed(
-                            conf,
-                            dst_dir_path,
-                            level + 1,
-                            permissions,
-                            semaphore,
-                        )
-                        .await
-                    });
+    /// Creates the configured entries inside the existing directory.
+    /// All spawned tasks are awaited before returning, even if some of them failed,
+    /// so no task outlives the build and keeps writing into the removed sandbox.
+    async fn build_content(
+        content: &[ConfigEntry],
+        dst_dir_path: Arc<PathBuf>,
+        level: u32,
+        settings: Arc<BuildSettings>,
+        semaphore: Arc<Semaphore>,
+    ) -> Result<()> {
+        let mut handles = vec![];
+        let mut errors = vec![];
 
-                    handles.push(handle);
-                }
-                ConfigEntry::CloneDirectory(conf) => {
-                    let conf = Arc::new(conf);
+        for config_entry in content {
+            // The build was cancelled by the failure of another entry.
+            if semaphore.is_closed() {
+                errors.push(FsTesterError::cancelled());
+                break;
+            }
 
-                    let handle = tokio::spawn(async move {
-                        Self::clone_directory(conf, dst_dir_path, level + 1, permissions, semaphore)
-                            .await
-                    });
+            let entry = config_entry.clone();
+            let task_semaphore = semaphore.clone();
+            let task_settings = settings.clone();
+            let dst_dir_path = dst_dir_path.clone();
 
-                    handles.push(handle);
+            let task = match entry {
+                ConfigEntry::Directory(conf) => Self::build_directory_with_content_boxed(
+                    Arc::new(conf),
+                    dst_dir_path,
+                    level + 1,
+                    task_settings,
+                    task_semaphore,
+                ),
+                ConfigEntry::CloneDirectory(conf) => async move {
+                    Self::clone_directory(
+                        Arc::new(conf),
+                        dst_dir_path,
+                        level + 1,
+                        task_settings,
+                        task_semaphore,
+                    )
+                    .await
                 }
-                ConfigEntry::File(conf) => {
-                    let conf = Arc::new(conf);
-
-                    let handle = tokio::spawn(async move {
-                        let _permit = semaphore
-                            .acquire()
-                            .await
-                            .expect("It seems that the semaphore has been closed.");
-                        Self::create_file(conf, dst_dir_path).await
-                    });
-
-                    handles.push(handle);
+                .boxed(),
+                ConfigEntry::File(conf) => async move {
+                    let _permit = task_semaphore.acquire().await?;
+                    Self::create_file(Arc::new(conf), dst_dir_path).await
                 }
-                ConfigEntry::Link(conf) => {
-                    let conf = Arc::new(conf);
+                .boxed(),
+                ConfigEntry::Link(conf) => async move {
+                    Self::create_link(Arc::new(conf), dst_dir_path, task_settings).await
+                }
+                .boxed(),
+                ConfigEntry::Symlink(conf) => {
+                    async move { Self::create_symlink(Arc::new(conf), dst_dir_path).await }.boxed()
+                }
+                ConfigEntry::Archive(conf) => async move {
+                    let _permit = task_semaphore.acquire().await?;
+                    Self::extract_archive(Arc::new(conf), dst_dir_path, level + 1).await
+                }
+                .boxed(),
+            };
+
+            // In the fail-fast mode the failed entry cancels the rest of the build,
+            // the entries waiting for the semaphore permit are not created.
+            let collect_errors = settings.collect_errors;
+            let task_semaphore = semaphore.clone();
+            let handle = tokio::spawn(async move {
+                let result = task.await;
+                if result.is_err() && !collect_errors {
+                    task_semaphore.close();
+                }
+                result
+            });
 
-                    let handle = tokio::spawn(async move {
-                        Self::create_link(conf, dst_dir_path, permissions).await
-                    });
+            handles.push((config_entry, handle));
+        }
 
-                    handles.push(handle);
-                }
+        for (config_entry, handle) in handles {
+            let error = match handle.await {
+                Ok(Ok(_)) => continue,
+                Ok(Err(err)) => err,
+                Err(err) => FsTesterError::from(err),
+            };
+            if !settings.collect_errors {
+                semaphore.close();
             }
+            errors.extend(error.within_entry(config_entry).into_build_errors());
         }
 
-        for handle in handles {
-            handle.await?.map_err(|mut err| {
-                if level == 0 {
-                    err.set_sandbox_dir(Some(String::from(dst_dir_path.to_string_lossy())));
-                }
-                err
-            })?;
+        Self::combine_errors(errors, settings.collect_errors)
+    }
+
+    /// Combines the errors of the entries: all of them in the collecting mode,
+    /// otherwise the first one. The entries cancelled by the failure of another entry
+    /// are reported only if nothing else failed.
+    fn combine_errors(mut errors: Vec<FsTesterError>, collect_errors: bool) -> Result<()> {
+        if errors.iter().any(|error| !error.is_cancelled()) {
+            errors.retain(|error| !error.is_cancelled());
+        }
+        if !collect_errors {
+            errors.truncate(1);
         }
 
-        Ok(dst_dir_path.to_string_lossy().into_owned())
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(FsTesterError::build_failed(errors)),
+        }
     }
 
     fn build_directory_with_content_boxed(
         conf: Arc<DirectoryConf>,
         parent_path: Arc<PathBuf>,
         level: u32,
-        permissions: Arc<Permissions>,
+        settings: Arc<BuildSettings>,
         semaphore: Arc<Semaphore>,
     ) -> BoxFuture<'static, Result<String>> {
         async move {
-            Self::build_directory_with_content(conf, parent_path, level, permissions, semaphore)
-                .await
+            Self::build_directory_with_content(conf, parent_path, level, settings, semaphore).await
         }
         .boxed()
     }
 
+    fn build_content_boxed<'a>(
+        content: &'a [ConfigEntry],
+        dst_dir_path: Arc<PathBuf>,
+        level: u32,
+        settings: Arc<BuildSettings>,
+        semaphore: Arc<Semaphore>,
+    ) -> BoxFuture<'a, Result<()>> {
+        async move { Self::build_content(content, dst_dir_path, level, settings, semaphore).await }
+            .boxed()
+    }
+
     fn copy_dir_boxed<'a>(
+        conf: Arc<CloneDirectoryConf>,
+        filter: CloneFilter,
         src_dir: Arc<PathBuf>,
         dst_path: Arc<PathBuf>,
-        permissions: Arc<Permissions>,
+        level: u32,
+        settings: Arc<BuildSettings>,
         semaphore: Arc<Semaphore>,
-    ) -> BoxFuture<'a, Result<String>> {
-        async move { Self::copy_dir(src_dir, dst_path, permissions, semaphore).await }.boxed()
+    ) -> BoxFuture<'a, Result<Vec<ClonedDirAttributes>>> {
+        async move {
+            Self::copy_dir(conf, filter, src_dir, dst_path, level, settings, semaphore).await
+        }
+        .boxed()
     }
 
     /// The configuration parser
@@ -372,10 +982,12 @@ impl FsTester {
     /// #           content:
     /// #             FileContent::InlineBytes(
     /// #               String::from("test").into_bytes(),
-    /// #             )
+    /// #             ),
+    /// #           ..Default::default()
     /// #         }
     /// #       )
     /// #     ),
+    /// #     ..Default::default()
     /// #   }
     /// # )));
     /// # assert_eq!(test_conf, FsTester::parse_config(simple_conf_str).unwrap());
@@ -398,10 +1010,12 @@ impl FsTester {
     /// #           content:
     /// #             FileContent::InlineBytes(
     /// #               String::from("test").into_bytes(),
-    /// #             )
+    /// #             ),
+    /// #           ..Default::default()
     /// #         }
     /// #       )
     /// #     ),
+    /// #     ..Default::default()
     /// #   }
     /// # )));
     /// #
@@ -411,10 +1025,8 @@ impl FsTester {
     pub fn parse_config(config_str: &str) -> Result<Configuration> {
         // detect format parse and return config instance
         match config_str.chars().next() {
-            Some('{') | Some('[') => {
-                serde_json::from_str(config_str).or_else(|error| Err(error.into()))
-            }
-            Some(_) => serde_yaml::from_str(config_str).or_else(|error| Err(error.into())),
+            Some('{') | Some('[') => serde_json::from_str(config_str).map_err(|error| error.into()),
+            Some(_) => serde_yaml::from_str(config_str).map_err(|error| error.into()),
             None => Err(FsTesterError::empty_config()),
         }
     }
@@ -422,63 +1034,241 @@ impl FsTester {
     /// Creates an RfsTester instance and construct test directory, files, and links by configuration.
     /// config_str - The configuration of the test directory is provided in the string in YAML or JSON format
     /// start_point - The directory name where the testing directory will be created should be specified.
-    ///               It should be present in the file system.
+    ///               It should be present in the file system. If it is empty, the directory from
+    ///               the RFS_TESTER_ROOT environment variable or the system temp directory is used.
+    ///
+    /// This constructor starts its own tokio runtime to build the sandbox,
+    /// so it should not be called from async code. Use [`FsTester::new_async`] there.
     pub fn new(config_str: &str, start_point: &str) -> Result<FsTester> {
-        let links_allowed =
-            env::var(LINKS_ALLOWED_VAR_NAME).unwrap_or_else(|_| "N".to_string()) != "N";
-        let permissions = Arc::new(Permissions { links_allowed });
+        Self::with_options(config_str, start_point, FsTesterOptions::default())
+    }
 
-        let config: Configuration = Self::parse_config(config_str)?;
+    /// Creates an RfsTester instance like [`FsTester::new`] with the additional options.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use rfs_tester::{FsTester, FsTesterOptions};
+    /// let options = FsTesterOptions {
+    ///     keep_on_failure: true,
+    ///     ..Default::default()
+    /// };
+    /// let tester = FsTester::with_options(
+    ///     "
+    ///     - !directory
+    ///         name: test_doc_with_options
+    ///         content: []
+    ///     ",
+    ///     ".",
+    ///     options,
+    /// )
+    /// .unwrap();
+    /// # let _ = tester;
+    /// ```
+    pub fn with_options(
+        config_str: &str,
+        start_point: &str,
+        options: FsTesterOptions,
+    ) -> Result<FsTester> {
+        let runtime = tokio::runtime::Runtime::new()?;
+        runtime.block_on(Self::with_options_async(config_str, start_point, options))
+    }
 
-        // The directory where the temporary test sandbox will be created.
-        let base_dir = if start_point.len() == 0 {
-            // If the starting point is not provided as an argument, we will use the current location.
-            PathBuf::from(".")
-        } else {
-            if Path::new(start_point).is_dir() {
-                PathBuf::from(start_point)
-            } else {
-                return Err(FsTesterError::should_start_from_directory());
-            }
-        };
+    /// The async version of [`FsTester::new`]. It builds the sandbox on the caller's runtime,
+    /// so it can be used inside `#[tokio::test]` functions.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use rfs_tester::{FsTester, FsTesterError};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FsTesterError> {
+    /// let tester = FsTester::new_async(
+    ///     "
+    ///     - !directory
+    ///         name: test_doc_new_async
+    ///         content:
+    ///           - !file
+    ///               name: test.txt
+    ///               content:
+    ///                 !inline_text test
+    ///     ",
+    ///     ".",
+    /// )
+    /// .await?;
+    ///
+    /// tester
+    ///     .perform_fs_test_async(async |dirname| {
+    ///         let file_path = std::path::PathBuf::from(dirname).join("test.txt");
+    ///         let content = tokio::fs::read_to_string(file_path).await?;
+    ///         assert_eq!(content, "test");
+    ///         Ok(())
+    ///     })
+    ///     .await;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn new_async(config_str: &str, start_point: &str) -> Result<FsTester> {
+        Self::with_options_async(config_str, start_point, FsTesterOptions::default()).await
+    }
 
-        // Checks if the configuration starts from a single config entry (Directory or CloneDirectory).
-        if config.0.len() != 1 {
-            return Err(FsTesterError::should_start_from_directory());
-        }
-        // After previous check we can take ConfigEntry
+    /// The async version of [`FsTester::with_options`].
+    pub async fn with_options_async(
+        config_str: &str,
+        start_point: &str,
+        options: FsTesterOptions,
+    ) -> Result<FsTester> {
+        let config: Configuration = Self::parse_config(config_str)?;
+        Self::from_config_with_options_async(config, start_point, options)
+            .await
+            .map_err(|err| err.locate_entry_in(config_str))
+    }
+
+    /// Creates an RfsTester instance from the configuration built in the code,
+    /// e.g. with the [`Tree`](crate::config::Tree) builder, without parsing a string.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use rfs_tester::config::Tree;
+    /// # use rfs_tester::FsTester;
+    /// let tester = FsTester::from_config(
+    ///     Tree::dir("test_from_config_doc").file("test.txt", "test").build(),
+    ///     ".",
+    /// )
+    /// .unwrap();
+    /// # let _ = tester;
+    /// ```
+    pub fn from_config(config: Configuration, start_point: &str) -> Result<FsTester> {
+        Self::from_config_with_options(config, start_point, FsTesterOptions::default())
+    }
+
+    /// Creates an RfsTester instance like [`FsTester::from_config`] with the additional options.
+    pub fn from_config_with_options(
+        config: Configuration,
+        start_point: &str,
+        options: FsTesterOptions,
+    ) -> Result<FsTester> {
+        let runtime = tokio::runtime::Runtime::new()?;
+        runtime.block_on(Self::from_config_with_options_async(
+            config,
+            start_point,
+            options,
+        ))
+    }
+
+    /// The async version of [`FsTester::from_config`].
+    pub async fn from_config_async(config: Configuration, start_point: &str) -> Result<FsTester> {
+        Self::from_config_with_options_async(config, start_point, FsTesterOptions::default()).await
+    }
+
+    /// The async version of [`FsTester::from_config_with_options`].
+    pub async fn from_config_with_options_async(
+        config: Configuration,
+        start_point: &str,
+        options: FsTesterOptions,
+    ) -> Result<FsTester> {
+        let links_allowed = env_flag(LINKS_ALLOWED_VAR_NAME);
+        let settings = Arc::new(BuildSettings {
+            links_allowed,
+            collect_errors: options.collect_errors,
+        });
+
+        // The directory where the temporary test sandbox will be created.
+        let base_dir = if start_point.is_empty() {
+            // If the starting point is not provided as an argument, the sandbox does not litter
+            // the current location, it is created in RFS_TESTER_ROOT or the system temp directory.
+            let base_dir = default_base_dir();
+            fs::create_dir_all(&base_dir).await?;
+            base_dir
+        } else if Path::new(start_point).is_dir() {
+            PathBuf::from(start_point)
+        } else {
+            return Err(FsTesterError::should_start_from_directory());
+        };
+
+        // Checks if the configuration starts from a single config entry (Directory or CloneDirectory).
+        if config.0.len() != 1 {
+            return Err(FsTesterError::should_start_from_directory());
+        }
+        // After previous check we can take ConfigEntry
         let root_config_entry = config
             .0
-            .iter()
-            .next()
+            .first()
             .expect("zero level of configuration should have only one entry");
         // And do verification if the configuration entry is Directory or CloneDirectory
+        if !matches!(
+            root_config_entry,
+            ConfigEntry::Directory(_) | ConfigEntry::CloneDirectory(_)
+        ) {
+            return Err(FsTesterError::should_start_from_directory());
+        }
+
+        // All problems of the configuration are reported before anything is created.
+        let issues = config.validate();
+        if !issues.is_empty() {
+            return Err(FsTesterError::invalid_config(issues));
+        }
+
+        let sandbox_dir = options
+            .naming
+            .create_sandbox(&base_dir, root_config_entry.name())?;
+        if let Err(err) = reaper::write_marker(&sandbox_dir) {
+            std::fs::remove_dir(&sandbox_dir)?;
+            return Err(err);
+        }
         let semaphore = Arc::new(Semaphore::new(SEMAPHORE_LIMIT));
-        let result = match root_config_entry {
-            ConfigEntry::Directory(conf) => {
-                let conf = Arc::new(conf.clone());
-                let runtime = tokio::runtime::Runtime::new()?;
-                runtime.block_on(Self::build_directory_with_content(
-                    conf.clone(),
-                    Arc::new(PathBuf::from(&base_dir)),
-                    0,
-                    permissions.clone(),
-                    semaphore.clone(),
-                ))
-            }
-            ConfigEntry::CloneDirectory(conf) => {
-                let conf = Arc::new(conf.clone());
-                let runtime = tokio::runtime::Runtime::new()?;
-                runtime.block_on(Self::clone_directory(
-                    conf.clone(),
-                    Arc::new(PathBuf::from(&base_dir)),
-                    0,
-                    permissions.clone(),
-                    semaphore.clone(),
-                ))
+        let result = async {
+            // The templates are rendered when the path of the sandbox is known.
+            let root_entry = TemplateContext::new(&sandbox_dir, &options.variables)?
+                .render_root(root_config_entry)?;
+
+            match root_entry {
+                ConfigEntry::Directory(conf) => {
+                    Self::build_directory_with_content(
+                        Arc::new(conf),
+                        Arc::new(sandbox_dir.clone()),
+                        0,
+                        settings.clone(),
+                        semaphore.clone(),
+                    )
+                    .await
+                }
+                ConfigEntry::CloneDirectory(conf) => {
+                    Self::clone_directory(
+                        Arc::new(conf),
+                        Arc::new(sandbox_dir.clone()),
+                        0,
+                        settings.clone(),
+                        semaphore.clone(),
+                    )
+                    .await
+                }
+                _ => unreachable!("the root entry should be a directory"),
             }
-            _ => return Err(FsTesterError::should_start_from_directory()),
-        };
+        }
+        .await
+        .map_err(|err| err.within_entry(root_config_entry))
+        .map_err(|mut err| {
+            // The sandbox directory exists even if nothing was built in it.
+            err.set_sandbox_dir(Some(sandbox_dir.to_string_lossy().into_owned()));
+            err
+        });
+
+        // Timestamps and permissions are applied when all entries are in place.
+        let result = result.and_then(|dst_dir_path| {
+            Self::apply_attributes(
+                root_config_entry,
+                Path::new(&dst_dir_path),
+                SystemTime::now(),
+            )
+            .map_err(|err| {
+                let mut err = err.within_entry(root_config_entry);
+                err.set_sandbox_dir(Some(dst_dir_path.clone()));
+                err
+            })?;
+            Ok(dst_dir_path)
+        });
 
         if let Err(error) = result {
             if let Some(dst_dir_path) = error.sandbox_dir() {
@@ -488,15 +1278,20 @@ impl FsTester {
                     && !Self::cmp_canonical_paths(".", &dst_dir_path)
                 {
                     // Delete a temporary directory if an error occured while filling it in.
-                    std::fs::remove_dir_all(&dst_dir_path)?;
+                    Self::remove_sandbox(Path::new(&dst_dir_path))?;
+                    reaper::remove_marker(Path::new(&dst_dir_path))?;
                 }
             }
             return Err(error);
         }
 
+        let base_dir = result.expect("This code branch should have a sandbox directory.");
         Ok(FsTester {
             config,
-            base_dir: result.expect("This code branch should have a sandbox directory."),
+            root: PathBuf::from(&base_dir),
+            base_dir,
+            options,
+            failure: Mutex::new(None),
         })
     }
 
@@ -539,24 +1334,436 @@ impl FsTester {
     {
         let dirname: &str = &self.base_dir;
 
-        if let Err(e) = test_proc(dirname) {
-            panic!("inner test has error: {}", e)
-        } else {
-            ()
+        self.check_test_result(panic::catch_unwind(AssertUnwindSafe(|| test_proc(dirname))));
+    }
+
+    /// Works like [`FsTester::perform_fs_test`], but the test unit receives
+    /// the sandbox directory as a `&Path`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use rfs_tester::FsTester;
+    /// let tester = FsTester::new(
+    ///     "
+    ///     - !directory
+    ///         name: test_doc_perform_fs_test_with_path
+    ///         content:
+    ///           - !file
+    ///               name: test.txt
+    ///               content:
+    ///                 !inline_text test
+    ///     ",
+    ///     ".",
+    /// )
+    /// .expect("Incorrect config");
+    /// tester.perform_fs_test_with_path(|root| {
+    ///     assert_eq!(std::fs::read_to_string(root.join("test.txt"))?, "test");
+    ///     Ok(())
+    /// });
+    /// ```
+    pub fn perform_fs_test_with_path<F>(&self, test_proc: F)
+    where
+        F: Fn(&Path) -> io::Result<()>,
+    {
+        let root: &Path = &self.root;
+
+        self.check_test_result(panic::catch_unwind(AssertUnwindSafe(|| test_proc(root))));
+    }
+
+    /// The async version of [`FsTester::perform_fs_test`]. The test unit is an async closure
+    /// which receives the name of the sandbox directory and is awaited on the caller's runtime.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use rfs_tester::{FsTester, FsTesterError};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), FsTesterError> {
+    /// # let tester = FsTester::new_async(
+    /// #     "
+    /// #     - !directory
+    /// #         name: test_doc_perform_fs_test_async
+    /// #         content:
+    /// #           - !file
+    /// #               name: test_from_cargo.toml
+    /// #               content:
+    /// #                 !original_file Cargo.toml
+    /// #     ",
+    /// #     ".",
+    /// # )
+    /// # .await?;
+    /// tester
+    ///     .perform_fs_test_async(async |dirname| {
+    ///         let inner_file_name = std::path::PathBuf::from(dirname).join("test_from_cargo.toml");
+    ///         let metadata = tokio::fs::metadata(inner_file_name).await?;
+    ///
+    ///         assert!(metadata.len() > 0);
+    ///         Ok(())
+    ///     })
+    ///     .await;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn perform_fs_test_async<F>(&self, test_proc: F)
+    where
+        F: AsyncFn(&str) -> io::Result<()>,
+    {
+        let dirname: &str = &self.base_dir;
+
+        self.check_test_result(AssertUnwindSafe(test_proc(dirname)).catch_unwind().await);
+    }
+
+    /// The async version of [`FsTester::perform_fs_test_with_path`].
+    pub async fn perform_fs_test_with_path_async<F>(&self, test_proc: F)
+    where
+        F: AsyncFn(&Path) -> io::Result<()>,
+    {
+        let root: &Path = &self.root;
+
+        self.check_test_result(AssertUnwindSafe(test_proc(root)).catch_unwind().await);
+    }
+
+    /// Runs the test unit without panicking and returns its result to the caller,
+    /// so FsTester can be used outside of `#[test]` functions, e.g. in benchmarks or examples.
+    /// The tester is consumed and the sandbox directory is removed when the test unit finishes,
+    /// whatever its result is (unless the sandbox is kept on failure by the options).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use rfs_tester::FsTester;
+    /// let tester = FsTester::new(
+    ///     "
+    ///     - !directory
+    ///         name: test_doc_try_perform_fs_test
+    ///         content:
+    ///           - !file
+    ///               name: test.txt
+    ///               content:
+    ///                 !inline_text test
+    ///     ",
+    ///     ".",
+    /// )
+    /// .expect("Incorrect config");
+    ///
+    /// let len = tester
+    ///     .try_perform_fs_test(|root| std::fs::metadata(root.join("test.txt")).map(|m| m.len()))
+    ///     .unwrap();
+    /// assert_eq!(len, 4);
+    /// ```
+    pub fn try_perform_fs_test<F, R, E>(self, test_proc: F) -> std::result::Result<R, E>
+    where
+        F: FnOnce(&Path) -> std::result::Result<R, E>,
+    {
+        let root: &Path = &self.root;
+
+        self.check_try_result(panic::catch_unwind(AssertUnwindSafe(|| test_proc(root))))
+    }
+
+    /// The async version of [`FsTester::try_perform_fs_test`].
+    pub async fn try_perform_fs_test_async<F, R, E>(self, test_proc: F) -> std::result::Result<R, E>
+    where
+        F: AsyncFnOnce(&Path) -> std::result::Result<R, E>,
+    {
+        let root: &Path = &self.root;
+
+        self.check_try_result(AssertUnwindSafe(test_proc(root)).catch_unwind().await)
+    }
+
+    /// Records the failure of the test unit and passes its result through.
+    /// Panics are propagated after they are recorded.
+    fn check_try_result<R, E>(
+        &self,
+        result: std::thread::Result<std::result::Result<R, E>>,
+    ) -> std::result::Result<R, E> {
+        match result {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(e)) => {
+                self.set_failure(String::from("inner test returned an error"));
+                Err(e)
+            }
+            Err(payload) => {
+                self.set_failure(String::from("inner test panicked"));
+                panic::resume_unwind(payload)
+            }
+        }
+    }
+
+    /// Records the failure of the test unit and propagates it as a panic.
+    fn check_test_result(&self, result: std::thread::Result<io::Result<()>>) {
+        match result {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => {
+                self.set_failure(format!("inner test has error: {}", e));
+                panic!("inner test has error: {}", e)
+            }
+            Err(payload) => {
+                self.set_failure(String::from("inner test panicked"));
+                panic::resume_unwind(payload)
+            }
+        }
+    }
+
+    fn set_failure(&self, reason: String) {
+        if let Ok(mut failure) = self.failure.lock() {
+            failure.get_or_insert(reason);
+        }
+    }
+
+    /// Returns the failure reason, if the test failed and the sandbox should be kept.
+    fn failure_to_keep(&self) -> Option<String> {
+        if !self.options.keep_on_failure() {
+            return None;
+        }
+
+        let failure = self
+            .failure
+            .lock()
+            .map(|failure| failure.clone())
+            .unwrap_or_else(|poisoned| poisoned.into_inner().clone());
+
+        failure.or_else(|| std::thread::panicking().then(|| String::from("test panicked")))
+    }
+
+    /// Keeps the sandbox for post-mortem debugging and writes the manifest if required.
+    /// The marker of the kept sandbox is removed, so the reaper does not touch it.
+    fn keep_sandbox(&self, failure: &str) {
+        if let Err(e) = reaper::remove_marker(&self.root) {
+            eprintln!("Failed to remove the sandbox marker due error: {}", e);
+        }
+        eprintln!(
+            "The sandbox directory {} is kept due to the test failure: {}",
+            &self.base_dir, failure
+        );
+
+        if self.options.write_manifest {
+            let manifest = Manifest {
+                sandbox: &self.base_dir,
+                failure,
+                config: &self.config,
+            };
+            let manifest_path = format!("{}.manifest.yaml", &self.base_dir);
+            let result = serde_yaml::to_string(&manifest)
+                .map_err(io::Error::other)
+                .and_then(|manifest| std::fs::write(&manifest_path, manifest));
+
+            if let Err(e) = result {
+                eprintln!(
+                    "Failed to write manifest {} due error: {}",
+                    manifest_path, e
+                );
+            }
+        }
+    }
+
+    /// The sandbox directory path.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Resolves the path relative to the sandbox directory.
+    ///
+    /// The path should be declared in the configuration. The content of cloned directories
+    /// is not described by the configuration, so paths inside them are accepted if they exist.
+    /// Returns an error if the path leads outside of the sandbox directory or it is not declared.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use rfs_tester::FsTester;
+    /// let tester = FsTester::new(
+    ///     "
+    ///     - !directory
+    ///         name: test_doc_path
+    ///         content:
+    ///           - !directory
+    ///               name: sub
+    ///               content:
+    ///                 - !file
+    ///                     name: file.txt
+    ///                     content: !empty
+    ///     ",
+    ///     ".",
+    /// )
+    /// .expect("Incorrect config");
+    ///
+    /// assert!(tester.path("sub/file.txt").unwrap().is_file());
+    /// assert!(tester.path("../file.txt").unwrap_err().is_sandbox_escape());
+    /// assert!(tester.path("sub/unknown.txt").unwrap_err().is_not_declared());
+    /// ```
+    pub fn path(&self, relative_path: impl AsRef<Path>) -> Result<PathBuf> {
+        let relative_path = relative_path.as_ref();
+
+        let mut names = vec![];
+        for component in relative_path.components() {
+            match component {
+                Component::Normal(name) => names.push(name),
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    if names.pop().is_none() {
+                        return Err(FsTesterError::path_escapes_sandbox(relative_path));
+                    }
+                }
+                Component::RootDir | Component::Prefix(_) => {
+                    return Err(FsTesterError::path_escapes_sandbox(relative_path));
+                }
+            }
+        }
+
+        let path = names
+            .iter()
+            .fold(self.root.clone(), |path, name| path.join(name));
+        let mut entry = self
+            .config
+            .0
+            .first()
+            .expect("the sandbox configuration should have the root entry");
+
+        for name in names {
+            match entry {
+                ConfigEntry::Directory(conf) => {
+                    entry = conf
+                        .content
+                        .iter()
+                        .find(|entry| entry.name() == name)
+                        .ok_or_else(|| FsTesterError::path_not_declared(relative_path))?;
+                }
+                ConfigEntry::CloneDirectory(_) | ConfigEntry::Archive(_) => {
+                    return self.check_cloned_path(path, relative_path);
+                }
+                _ => return Err(FsTesterError::path_not_declared(relative_path)),
+            }
+        }
+
+        Ok(path)
+    }
+
+    /// Checks that the path inside a cloned directory or an extracted archive exists
+    /// and does not lead outside of the sandbox through their symbolic links.
+    fn check_cloned_path(&self, path: PathBuf, relative_path: &Path) -> Result<PathBuf> {
+        if std::fs::symlink_metadata(&path).is_err() {
+            return Err(FsTesterError::path_not_declared(relative_path));
+        }
+
+        let parent = path.parent().unwrap_or(&self.root);
+        if !std::fs::canonicalize(parent)?.starts_with(std::fs::canonicalize(&self.root)?) {
+            return Err(FsTesterError::path_escapes_sandbox(relative_path));
+        }
+
+        Ok(path)
+    }
+
+    /// Compares the sandbox content with the expected configuration in YAML or JSON format
+    /// and returns the found differences. The root entry of the expected configuration
+    /// describes the sandbox directory itself, so its name is not compared.
+    ///
+    /// Files are compared byte by byte, symbolic links by their targets,
+    /// and cloned directories with the content of their source directories.
+    /// The entries which are present in the sandbox but not in the configuration
+    /// are reported as extra.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use rfs_tester::{FsTester, FsTesterError};
+    /// # fn main() -> Result<(), FsTesterError> {
+    /// let tester = FsTester::new(
+    ///     "
+    ///     - !directory
+    ///         name: test_doc_compare
+    ///         content:
+    ///           - !file
+    ///               name: test.txt
+    ///               content:
+    ///                 !inline_text test
+    ///     ",
+    ///     ".",
+    /// )?;
+    ///
+    /// tester.perform_fs_test(|dirname| {
+    ///     std::fs::write(std::path::PathBuf::from(dirname).join("test.txt"), "changed")
+    /// });
+    ///
+    /// let diff = tester.compare(
+    ///     "
+    ///     - !directory
+    ///         name: expected
+    ///         content:
+    ///           - !file
+    ///               name: test.txt
+    ///               content:
+    ///                 !inline_text test
+    ///     ",
+    /// )?;
+    /// assert_eq!(diff.entries.len(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn compare(&self, expected_config_str: &str) -> Result<FsDiff> {
+        let expected = Self::parse_config(expected_config_str)?;
+        self.compare_config(&expected)
+    }
+
+    /// Compares the sandbox content with the expected configuration.
+    /// See [`FsTester::compare`] for details.
+    pub fn compare_config(&self, expected: &Configuration) -> Result<FsDiff> {
+        let root_entry = match expected.0.as_slice() {
+            [root_entry] => root_entry,
+            _ => return Err(FsTesterError::should_start_from_directory()),
+        };
+
+        let root_entry = TemplateContext::new(&self.root, &self.options.variables)?
+            .render_root(root_entry)
+            .map_err(|err| err.within_entry(root_entry))?;
+        let mut diff = FsDiff::default();
+        let sandbox_dir = Path::new(&self.base_dir);
+        match &root_entry {
+            ConfigEntry::Directory(conf) => {
+                diff.compare_config_dir(&conf.content, sandbox_dir, Path::new(""))?
+            }
+            ConfigEntry::CloneDirectory(conf) => {
+                diff.compare_clone_dir(conf, sandbox_dir, Path::new(""))?
+            }
+            _ => return Err(FsTesterError::should_start_from_directory()),
+        }
+
+        Ok(diff)
+    }
+
+    /// Asserts that the sandbox content matches the expected configuration.
+    /// It panics with the rendered tree of differences otherwise.
+    /// See [`FsTester::compare`] for details.
+    pub fn assert_matches(&self, expected_config_str: &str) {
+        match self.compare(expected_config_str) {
+            Ok(diff) if diff.is_empty() => {}
+            Ok(diff) => panic!(
+                "sandbox content does not match the expected configuration:\n{}",
+                diff
+            ),
+            Err(e) => panic!("sandbox comparison has error: {}", e),
         }
     }
 }
 
 impl Drop for FsTester {
     /// The drop handler checks to see if the sandbox directory has been created and removes it if it has.
+    /// The sandbox is kept if the test failed and the keep-on-failure mode is enabled.
     fn drop(&mut self) {
+        if let Some(failure) = self.failure_to_keep() {
+            self.keep_sandbox(&failure);
+            return;
+        }
+
         let sandbox_dir = &self.base_dir;
 
         // Protecting the current path from accidental removal
         if !Self::cmp_canonical_paths("/", sandbox_dir)
             && !Self::cmp_canonical_paths(".", sandbox_dir)
         {
-            if let Err(e) = std::fs::remove_dir_all(&self.base_dir) {
+            let result =
+                Self::remove_sandbox(&self.root).and_then(|_| reaper::remove_marker(&self.root));
+            if let Err(e) = result {
                 eprintln!(
                     "Failed to delete directory {} due error: {}",
                     &self.base_dir, e
@@ -570,10 +1777,17 @@ impl Drop for FsTester {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+    use std::env;
     use std::os::unix::fs::MetadataExt;
 
-    use crate::rfs::config::{file_conf::FileConf, link_conf::LinkConf};
+    use crate::rfs::config::{
+        file_conf::FileConf, link_conf::LinkConf, ConfigIssue, DirBuilder, IssueKind, SymlinkConf,
+        Tree,
+    };
+    use crate::rfs::fs_diff::{DiffEntry, Difference, EntryKind};
     use crate::rfs::fs_tester_error::Result;
+    use crate::rfs::sandbox_naming::SandboxNaming;
 
     use super::*;
 
@@ -643,6 +1857,138 @@ mod tests {
         assert!(res.is_err());
     }
 
+    #[test]
+    fn constructor_should_use_default_base_dir_when_start_point_is_empty() -> Result<()> {
+        let tester = FsTester::new(YAML_DIR_WITH_EMPTY_FILE, "")?;
+
+        assert_eq!(tester.root().parent(), Some(default_base_dir().as_path()));
+        assert!(tester.root().join("test.txt").is_file());
+        Ok(())
+    }
+
+    #[test]
+    fn constructor_should_not_create_sandbox_for_invalid_config() {
+        let options = FsTesterOptions {
+            naming: SandboxNaming::Exact(String::from("test_invalid_config_sandbox")),
+            ..Default::default()
+        };
+        let config = Tree::dir("root")
+            .file("test.txt", "")
+            .file("test.txt", "")
+            .file("../outside.txt", "")
+            .build();
+
+        let result = FsTester::from_config_with_options(config, ".", options);
+
+        assert!(result
+            .is_err_and(|error| error.is_sandbox_escape() && error.config_issues().len() == 2));
+        assert!(!Path::new("test_invalid_config_sandbox").exists());
+    }
+
+    #[test]
+    fn build_error_should_name_the_failed_entry() {
+        let options = FsTesterOptions {
+            naming: SandboxNaming::Exact(String::from("test_build_error_entry_sandbox")),
+            ..Default::default()
+        };
+        // The overlay file can not replace the cloned directory which is not removed.
+        let config_str = "
+- !directory
+    name: test_build_error_entry
+    content:
+      - !clone_directory
+          name: copy
+          source: src
+          content:
+            - !file
+                name: rfs
+                content: !empty
+";
+
+        let Err(error) = FsTester::with_options(config_str, ".", options) else {
+            panic!("the overlay file should not replace the cloned directory");
+        };
+
+        assert!(error.is_io());
+        assert_eq!(
+            error.entry_path(),
+            Some(Path::new("test_build_error_entry/copy/rfs"))
+        );
+        assert_eq!(error.entry_kind(), Some("file"));
+        assert_eq!(error.entry_source(), None);
+        assert_eq!((error.line(), error.column()), (10, 17));
+        assert!(!Path::new("test_build_error_entry_sandbox").exists());
+    }
+
+    /// Both overlay files can not replace the cloned directories which are not removed.
+    fn config_with_two_broken_entries() -> Configuration {
+        let broken_clone = |d: DirBuilder| {
+            d.entry(CloneDirectoryConf {
+                name: String::from("copy"),
+                source: String::from("src"),
+                content: Tree::dir("overlay").file("rfs", "").into_conf().content,
+                ..Default::default()
+            })
+        };
+        Tree::dir("test_collect_errors")
+            .dir("first", broken_clone)
+            .file("test.txt", "test")
+            .dir("second", broken_clone)
+            .build()
+    }
+
+    #[test]
+    fn collecting_mode_should_report_all_failed_entries() {
+        let options = FsTesterOptions {
+            naming: SandboxNaming::Exact(String::from("test_collect_errors_sandbox")),
+            collect_errors: true,
+            ..Default::default()
+        };
+
+        let Err(error) =
+            FsTester::from_config_with_options(config_with_two_broken_entries(), ".", options)
+        else {
+            panic!("the overlay files should not replace the cloned directories");
+        };
+
+        let paths: Vec<_> = error
+            .build_errors()
+            .iter()
+            .map(|error| error.entry_path())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                Some(Path::new("test_collect_errors/first/copy/rfs")),
+                Some(Path::new("test_collect_errors/second/copy/rfs")),
+            ]
+        );
+        assert!(error.is_io());
+        assert!(format!("{}", error).starts_with("2 entries failed to be built:"));
+        assert!(!Path::new("test_collect_errors_sandbox").exists());
+    }
+
+    #[test]
+    fn fail_fast_mode_should_report_the_first_failed_entry() {
+        let options = FsTesterOptions {
+            naming: SandboxNaming::Exact(String::from("test_fail_fast_sandbox")),
+            ..Default::default()
+        };
+
+        let Err(error) =
+            FsTester::from_config_with_options(config_with_two_broken_entries(), ".", options)
+        else {
+            panic!("the overlay files should not replace the cloned directories");
+        };
+
+        assert!(error.build_errors().is_empty());
+        assert!(error.is_io());
+        assert!(error
+            .entry_path()
+            .is_some_and(|path| path.ends_with("copy/rfs")));
+        assert!(!Path::new("test_fail_fast_sandbox").exists());
+    }
+
     #[test]
     fn constructor_should_return_error_when_base_dir_not_found() -> Result<()> {
         let res = FsTester::new(YAML_DIR_WITH_EMPTY_FILE, "unexisting_directory");
@@ -726,7 +2072,8 @@ mod tests {
             .unwrap(),
             Configuration(vec!(ConfigEntry::Directory(DirectoryConf {
                 name: String::from("simple_test_dir"),
-                content: Vec::new()
+                content: Vec::new(),
+                ..Default::default()
             }))),
         );
     }
@@ -736,6 +2083,7 @@ mod tests {
         let conf: Configuration = Configuration(vec![ConfigEntry::Directory(DirectoryConf {
             name: String::from("json_serialization_test_dir"),
             content: Vec::new(),
+            ..Default::default()
         })]);
 
         assert_eq!(
@@ -751,7 +2099,8 @@ mod tests {
         assert_eq!(
             Configuration(vec!(ConfigEntry::Directory(DirectoryConf {
                 name: String::from("yaml_serialization_test_dir"),
-                content: Vec::new()
+                content: Vec::new(),
+                ..Default::default()
             }))),
             FsTester::parse_config(
                 "---\n- !directory\n    name: \"yaml_serialization_test_dir\"\n    content: []\n"
@@ -780,7 +2129,9 @@ mod tests {
             content: vec![ConfigEntry::File(FileConf {
                 name: String::from("test.txt"),
                 content: FileContent::InlineBytes(String::from("test").into_bytes()),
+                ..Default::default()
             })],
+            ..Default::default()
         })]);
 
         assert_eq!(test_conf, FsTester::parse_config(simple_conf_str).unwrap());
@@ -802,12 +2153,71 @@ mod tests {
             content: vec![ConfigEntry::File(FileConf {
                 name: String::from("test.txt"),
                 content: FileContent::InlineText(String::from("test")),
+                ..Default::default()
             })],
+            ..Default::default()
         })]);
 
         assert_eq!(test_conf, FsTester::parse_config(simple_conf_str).unwrap());
     }
 
+    #[test]
+    fn parser_should_decode_encoded_inline_content() -> Result<()> {
+        let config = FsTester::parse_config(
+            r#"
+- !directory
+    name: test
+    content:
+      - !file
+          name: base64.bin
+          content: !inline_base64 AJ+S lg==
+      - !file
+          name: hex.bin
+          content: !inline_hex "00 9f 92 96"
+      - !file
+          name: escaped.bin
+          content: !inline_escaped '\x00\x9f\x92\x96'
+"#,
+        )?;
+
+        let data = vec![0, 159, 146, 150];
+        assert_eq!(
+            config,
+            Tree::dir("test")
+                .file("base64.bin", FileContent::InlineBase64(data.clone()))
+                .file("hex.bin", FileContent::InlineHex(data.clone()))
+                .file("escaped.bin", FileContent::InlineEscaped(data))
+                .build()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parser_should_report_decoding_errors_with_location() {
+        let yaml_error = FsTester::parse_config(
+            "
+- !directory
+    name: test
+    content:
+      - !file
+          name: test.bin
+          content: !inline_hex 0g
+",
+        )
+        .unwrap_err();
+        let json_error = FsTester::parse_config(
+            r#"[{"file":{"name":"test.bin","content":{"inline_base64":"A*=="}}}]"#,
+        )
+        .unwrap_err();
+
+        assert!(yaml_error.is_syntax());
+        assert_eq!(yaml_error.line(), 7);
+        assert!(format!("{}", yaml_error).contains("'g' is not a hex digit"));
+        assert!(json_error.is_syntax());
+        assert_eq!(json_error.line(), 1);
+        assert!(json_error.column() > 0);
+    }
+
     #[test]
     fn parser_should_accept_yaml_config_with_clone_directory() {
         let simple_conf_str = "
@@ -818,6 +2228,7 @@ mod tests {
         let test_conf = Configuration(vec![ConfigEntry::CloneDirectory(CloneDirectoryConf {
             name: String::from("test_yaml_config_with_clone_directory"),
             source: String::from("src"),
+            ..Default::default()
         })]);
 
         assert_eq!(test_conf, FsTester::parse_config(simple_conf_str).unwrap());
@@ -839,7 +2250,9 @@ mod tests {
             content: vec![ConfigEntry::File(FileConf {
                 name: String::from("test.txt"),
                 content: FileContent::OriginalFile(String::from("sample_test.txt")),
+                ..Default::default()
             })],
+            ..Default::default()
         })]);
 
         assert_eq!(test_conf, FsTester::parse_config(simple_conf_str).unwrap());
@@ -861,7 +2274,9 @@ mod tests {
             content: vec![ConfigEntry::File(FileConf {
                 name: String::from("test.txt"),
                 content: FileContent::Empty,
+                ..Default::default()
             })],
+            ..Default::default()
         })]);
 
         assert_eq!(test_conf, FsTester::parse_config(simple_conf_str).unwrap());
@@ -875,7 +2290,9 @@ mod tests {
             content: vec![ConfigEntry::File(FileConf {
                 name: String::from("test.txt"),
                 content: FileContent::InlineBytes(String::from("test").into_bytes()),
+                ..Default::default()
             })],
+            ..Default::default()
         })]);
 
         assert_eq!(test_conf, FsTester::parse_config(simple_conf_str).unwrap());
@@ -905,12 +2322,14 @@ mod tests {
                 ConfigEntry::File(FileConf {
                     name: String::from("test.txt"),
                     content: FileContent::InlineBytes(String::from("test").into_bytes()),
+                    ..Default::default()
                 }),
                 ConfigEntry::Link(LinkConf {
                     name: String::from("test_link.txt"),
                     target: String::from("test.txt"),
                 }),
             ],
+            ..Default::default()
         })]);
 
         let parsed_config = FsTester::parse_config(simple_conf_str).unwrap();
@@ -923,6 +2342,7 @@ mod tests {
         let conf: Configuration = Configuration(vec![ConfigEntry::Directory(DirectoryConf {
             name: String::from("test_serialization_for_simple_yaml_config"),
             content: Vec::new(),
+            ..Default::default()
         })]);
 
         assert_eq!(
@@ -996,7 +2416,7 @@ mod tests {
                 assert!(m_data.is_dir());
             })?;
 
-            let fs_tester_file = PathBuf::from(rfs_dir_path).join("fs_tester.rs");
+            let fs_tester_file = rfs_dir_path.join("fs_tester.rs");
             fs::metadata(fs_tester_file).map(|m_data| {
                 assert!(m_data.size() > 0);
             })?;
@@ -1037,7 +2457,7 @@ mod tests {
                 assert!(m_data.is_dir());
             })?;
 
-            let fs_tester_file = PathBuf::from(rfs_dir_path).join("fs_tester.rs");
+            let fs_tester_file = rfs_dir_path.join("fs_tester.rs");
             fs::metadata(fs_tester_file).map(|m_data| {
                 assert!(m_data.size() > 0);
             })?;
@@ -1047,23 +2467,81 @@ mod tests {
         Ok(())
     }
 
-    #[test]
-    fn create_test_dir_with_link_dependent_from_links_allowed_env_var() {
-        if env::var("LINKS_ALLOWED") == Ok("Y".to_string()) {
-            let tester_result = FsTester::new(YAML_DIR_WITH_LINK, ".");
-
-            assert!(tester_result.is_ok());
-
-            tester_result.unwrap().perform_fs_test(|dirname| {
-                let file_path = PathBuf::from(dirname).join("cargo_link");
-                let metadata = std::fs::metadata(file_path);
+    #[tokio::test]
+    async fn async_constructor_should_work_inside_runtime() -> Result<()> {
+        let tester = FsTester::new_async(YAML_DIR_WITH_TEST_FILE_FROM_CARGO_TOML, ".").await?;
 
-                assert!(metadata.is_ok());
-                assert!(metadata.unwrap().is_file());
+        tester
+            .perform_fs_test_async(async |dirname| {
+                let inner_file_name = PathBuf::from(dirname).join("test_from_cargo.toml");
+                let metadata = fs::metadata(inner_file_name).await?;
 
+                assert!(metadata.len() > 0);
                 Ok(())
-            });
-        } else {
+            })
+            .await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn async_constructor_should_clone_directory() -> Result<()> {
+        let simple_conf_str = "
+        - !clone_directory
+            name: test_async_clone_directory
+            source: src
+        ";
+
+        let tester = FsTester::new_async(simple_conf_str, ".").await?;
+
+        tester
+            .perform_fs_test_async(async |dirname| {
+                let file_path = PathBuf::from(dirname).join("lib.rs");
+                assert!(fs::metadata(file_path).await?.size() > 0);
+                Ok(())
+            })
+            .await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn async_constructor_should_return_error_when_base_dir_not_found() {
+        let res = FsTester::new_async(YAML_DIR_WITH_EMPTY_FILE, "unexisting_directory").await;
+
+        assert!(res.is_err_and(|error| error.is_should_start_from_directory()));
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "inner test has error")]
+    async fn async_perform_fs_test_should_panic_on_inner_error() {
+        let tester = FsTester::new_async(YAML_DIR_WITH_EMPTY_FILE, ".")
+            .await
+            .expect("Correct config should be here");
+
+        tester
+            .perform_fs_test_async(async |dirname| {
+                fs::metadata(PathBuf::from(dirname).join("unexisting_file.txt")).await?;
+                Ok(())
+            })
+            .await;
+    }
+
+    #[test]
+    fn create_test_dir_with_link_dependent_from_links_allowed_env_var() {
+        if env::var("LINKS_ALLOWED") == Ok("Y".to_string()) {
+            let tester_result = FsTester::new(YAML_DIR_WITH_LINK, ".");
+
+            assert!(tester_result.is_ok());
+
+            tester_result.unwrap().perform_fs_test(|dirname| {
+                let file_path = PathBuf::from(dirname).join("cargo_link");
+                let metadata = std::fs::metadata(file_path);
+
+                assert!(metadata.is_ok());
+                assert!(metadata.unwrap().is_file());
+
+                Ok(())
+            });
+        } else {
             let tester_result = FsTester::new(YAML_DIR_WITH_LINK, ".");
             if let Err(error) = tester_result {
                 assert!(error.is_not_allowed_settings());
@@ -1073,6 +2551,1628 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parser_should_accept_yaml_config_with_symlink() {
+        let simple_conf_str = "
+    - !directory
+        name: test_yaml_config_with_symlink
+        content:
+        - !symlink
+            name: test_symlink
+            target: ../test.txt
+    ";
+        let test_conf = Configuration(vec![ConfigEntry::Directory(DirectoryConf {
+            name: String::from("test_yaml_config_with_symlink"),
+            content: vec![ConfigEntry::Symlink(SymlinkConf {
+                name: String::from("test_symlink"),
+                target: String::from("../test.txt"),
+                ..Default::default()
+            })],
+            ..Default::default()
+        })]);
+
+        assert_eq!(test_conf, FsTester::parse_config(simple_conf_str).unwrap());
+    }
+
+    #[test]
+    fn constructor_should_reject_symlinks_outside_of_sandbox_without_opt_in() {
+        let conf = r#"
+        - !directory
+            name: test_dir_with_outside_symlinks
+            content:
+                - !directory
+                    name: inner
+                    content:
+                        - !symlink
+                            name: escaping_link
+                            target: ../../Cargo.toml
+                - !symlink
+                    name: absolute_link
+                    target: /
+        "#;
+
+        let result = FsTester::new(conf, ".");
+
+        assert!(result.is_err_and(|error| error.is_sandbox_escape()
+            && error.config_issues()
+                == [
+                    ConfigIssue {
+                        path: PathBuf::from("test_dir_with_outside_symlinks/inner/escaping_link"),
+                        kind: IssueKind::SymlinkOutsideSandbox(PathBuf::from("../../Cargo.toml")),
+                    },
+                    ConfigIssue {
+                        path: PathBuf::from("test_dir_with_outside_symlinks/absolute_link"),
+                        kind: IssueKind::SymlinkOutsideSandbox(PathBuf::from("/")),
+                    },
+                ]));
+    }
+
+    #[test]
+    fn create_test_dir_with_symlinks_without_links_allowed_env_var() -> Result<()> {
+        let conf = r#"
+        - !directory
+            name: test_dir_with_symlinks
+            content:
+                - !file
+                    name: test.txt
+                    content:
+                        !inline_text "test"
+                - !directory
+                    name: inner
+                    content:
+                        - !symlink
+                            name: relative_file_link
+                            target: ../test.txt
+                - !symlink
+                    name: dir_link
+                    target: inner
+                - !symlink
+                    name: absolute_link
+                    target: /
+                    allow_outside: true
+                - !symlink
+                    name: dangling_link
+                    target: unexisting_file.txt
+                - !symlink
+                    name: looping_link
+                    target: looping_link
+        "#;
+
+        let tester = FsTester::new(conf, ".")?;
+        tester.perform_fs_test(|dirname| {
+            let dir_path = PathBuf::from(dirname);
+
+            let relative_file_link = dir_path.join("inner").join("relative_file_link");
+            assert!(std::fs::symlink_metadata(&relative_file_link)?.is_symlink());
+            assert_eq!(std::fs::read_to_string(relative_file_link)?, "test");
+
+            let dir_link = dir_path.join("dir_link");
+            assert!(std::fs::symlink_metadata(&dir_link)?.is_symlink());
+            assert!(std::fs::metadata(&dir_link)?.is_dir());
+
+            assert_eq!(
+                std::fs::read_link(dir_path.join("absolute_link"))?,
+                PathBuf::from("/")
+            );
+
+            let dangling_link = dir_path.join("dangling_link");
+            assert!(std::fs::symlink_metadata(&dangling_link)?.is_symlink());
+            assert!(std::fs::metadata(&dangling_link).is_err());
+
+            let looping_link = dir_path.join("looping_link");
+            assert!(std::fs::symlink_metadata(&looping_link)?.is_symlink());
+            assert!(std::fs::metadata(&looping_link).is_err());
+
+            Ok(())
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn parser_should_accept_mode_as_yaml_octal_and_json_string() {
+        let yaml_conf_str = "
+    - !directory
+        name: test_yaml_config_with_mode
+        mode: 0o755
+        content:
+        - !file
+            name: test.txt
+            content: !empty
+            mode: 0o644
+    ";
+        let json_conf_str = r#"[{"directory":{"name":"test_yaml_config_with_mode","mode":"0755","content":[{"file":{"name":"test.txt","content":"empty","mode":"0o644"}}]}}]"#;
+        let yaml_without_prefix_conf_str = "
+    - !directory
+        name: test_yaml_config_with_mode
+        mode: 755
+        content:
+        - !file
+            name: test.txt
+            content: !empty
+            mode: 0644
+    ";
+        let test_conf = Configuration(vec![ConfigEntry::Directory(DirectoryConf {
+            name: String::from("test_yaml_config_with_mode"),
+            content: vec![ConfigEntry::File(FileConf {
+                name: String::from("test.txt"),
+                content: FileContent::Empty,
+                mode: Some(0o644),
+                ..Default::default()
+            })],
+            mode: Some(0o755),
+            ..Default::default()
+        })]);
+
+        assert_eq!(test_conf, FsTester::parse_config(yaml_conf_str).unwrap());
+        assert_eq!(test_conf, FsTester::parse_config(json_conf_str).unwrap());
+        assert_eq!(
+            test_conf,
+            FsTester::parse_config(yaml_without_prefix_conf_str).unwrap()
+        );
+        for serialized in [
+            serde_json::to_string(&test_conf).unwrap(),
+            serde_yaml::to_string(&test_conf).unwrap(),
+        ] {
+            assert_eq!(test_conf, FsTester::parse_config(&serialized).unwrap());
+        }
+    }
+
+    #[test]
+    fn parser_should_reject_invalid_mode() {
+        let conf_str = "
+    - !directory
+        name: test_yaml_config_with_invalid_mode
+        content: []
+        mode: \"0o999\"
+    ";
+
+        let error = FsTester::parse_config(conf_str).expect_err("mode is not octal");
+        assert!(error.is_syntax());
+
+        // The JSON number would be taken as a decimal number.
+        let json_conf_str = r#"[{"directory":{"name":"test_json_config_with_number_mode","mode":644,"content":[]}}]"#;
+        let error = FsTester::parse_config(json_conf_str).expect_err("mode is a number");
+        assert!(error.is_syntax());
+        assert!(error.to_string().contains("octal string"), "{}", error);
+    }
+
+    const YAML_DIR_WITH_GENERATED_CONTENT: &str = "
+    - !directory
+        name: test_generated_content
+        content:
+          - !file
+              name: random.bin
+              content: !random { size: 300KiB, seed: 42 }
+          - !file
+              name: same_random.bin
+              content: !random { size: 300KiB, seed: 42 }
+          - !file
+              name: repeat.txt
+              content: !repeat { pattern: abc, size: 100001 }
+          - !file
+              name: zeros.bin
+              content: !zeros { size: 2 MB }
+    ";
+
+    #[test]
+    fn parser_should_accept_sizes_of_generated_content() -> Result<()> {
+        let config = FsTester::parse_config(
+            r#"[{"directory":{"name":"test","content":[{"file":{"name":"zeros.bin","content":{"zeros":{"size":"1 KiB"}}}}]}}]"#,
+        )?;
+
+        assert_eq!(
+            config,
+            Tree::dir("test")
+                .file("zeros.bin", FileContent::Zeros { size: 1024 })
+                .build()
+        );
+        assert!(FsTester::parse_config(
+            "- !file\n    name: test\n    content: !zeros { size: 1.5GB }"
+        )
+        .is_err_and(|error| error.is_syntax()));
+        Ok(())
+    }
+
+    #[test]
+    fn create_test_dir_with_generated_content() -> Result<()> {
+        let tester = FsTester::new(YAML_DIR_WITH_GENERATED_CONTENT, ".")?;
+
+        tester.perform_fs_test_with_path(|root| {
+            let random = std::fs::read(root.join("random.bin"))?;
+            let repeat = std::fs::read(root.join("repeat.txt"))?;
+            let zeros = std::fs::read(root.join("zeros.bin"))?;
+
+            assert_eq!(random.len(), 300 * 1024);
+            assert_eq!(random, std::fs::read(root.join("same_random.bin"))?);
+            assert_eq!(repeat.len(), 100_001);
+            assert!(repeat.starts_with(b"abcabc") && repeat.ends_with(b"cab"));
+            assert_eq!(zeros.len(), 2_000_000);
+            assert!(zeros.iter().all(|byte| *byte == 0));
+            Ok(())
+        });
+        assert!(tester.compare_config(&tester.config)?.is_empty());
+        Ok(())
+    }
+
+    const YAML_DIR_WITH_SPARSE_FILE: &str = "
+    - !directory
+        name: test_sparse_file
+        content:
+          - !file
+              name: sparse.img
+              content: !sparse
+                size: 64MiB
+                extents:
+                  - offset: 0
+                    content: !inline_text header
+                  - offset: 32MiB
+                    content: !repeat { pattern: data, size: 8KiB }
+    ";
+
+    #[test]
+    fn create_test_dir_with_sparse_file_and_clone_it() -> Result<()> {
+        let tester = FsTester::new(YAML_DIR_WITH_SPARSE_FILE, ".")?;
+        let clone = FsTester::from_config(
+            Tree::dir("test_sparse_clone")
+                .entry(CloneDirectoryConf {
+                    name: String::from("copy"),
+                    source: tester.base_dir.clone(),
+                    preserve_holes: true,
+                    ..Default::default()
+                })
+                .build(),
+            ".",
+        )?;
+
+        for path in [
+            tester.root().join("sparse.img"),
+            clone.root().join("copy/sparse.img"),
+        ] {
+            let metadata = std::fs::metadata(&path)?;
+            let content = std::fs::read(&path)?;
+
+            assert_eq!(metadata.len(), 64 << 20);
+            // The holes do not take the disk blocks, which are 512 bytes long.
+            assert!(metadata.blocks() * 512 < 1 << 20);
+            assert!(content.starts_with(b"header\0"));
+            assert_eq!(&content[(32 << 20)..(32 << 20) + 8], b"datadata");
+            assert!(content[(32 << 20) + 8192..].iter().all(|byte| *byte == 0));
+        }
+        assert!(tester.compare_config(&tester.config)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn sparse_extent_of_unknown_length_should_not_exceed_the_file_size() {
+        let config = Tree::dir("test_sparse_overflow")
+            .file(
+                "sparse.img",
+                FileContent::Sparse {
+                    size: 8,
+                    extents: vec![crate::rfs::config::file_content::SparseExtent {
+                        offset: 4,
+                        content: FileContent::Gzip {
+                            content: Box::new(FileContent::InlineBytes(gzip(b"too long"))),
+                        },
+                    }],
+                },
+            )
+            .build();
+
+        let Err(error) = FsTester::from_config(config, ".") else {
+            panic!("the extent exceeding the file size should fail the build");
+        };
+        assert!(error.to_string().contains("offset 4 overlaps"), "{}", error);
+        assert_eq!(
+            error.entry_path(),
+            Some(Path::new("test_sparse_overflow/sparse.img"))
+        );
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    /// Builds the tar archive, the entries with a target are symbolic links.
+    fn tar_archive(entries: &[(&str, &str, Option<&str>)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(vec![]);
+        for (path, data, target) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_mode(0o755);
+            header.set_size(0);
+            match target {
+                Some(target) => {
+                    header.set_entry_type(tar::EntryType::Symlink);
+                    builder.append_link(&mut header, path, target).unwrap();
+                }
+                None => {
+                    header.set_size(data.len() as u64);
+                    builder
+                        .append_data(&mut header, path, data.as_bytes())
+                        .unwrap();
+                }
+            }
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn zip_archive(entries: &[(&str, &str)]) -> Vec<u8> {
+        use std::io::Write;
+
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        let mut writer = zip::ZipWriter::new(io::Cursor::new(vec![]));
+        for (path, data) in entries {
+            writer.start_file(*path, options).unwrap();
+            writer.write_all(data.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn parser_should_accept_archives_and_gzip_content() -> Result<()> {
+        let config = FsTester::parse_config(
+            r#"
+- !directory
+    name: test
+    content:
+      - !archive
+          name: project
+          source: !original_file fixtures/project.tar.gz
+      - !file
+          name: test.txt
+          content: !gzip
+              content: !inline_base64 H4sIAAAAAAACAytJLS4BAAx+f9gEAAAA
+"#,
+        )?;
+
+        assert_eq!(
+            config,
+            Tree::dir("test")
+                .archive(
+                    "project",
+                    FileContent::OriginalFile(String::from("fixtures/project.tar.gz"))
+                )
+                .file(
+                    "test.txt",
+                    FileContent::Gzip {
+                        content: Box::new(FileContent::InlineBase64(vec![
+                            31, 139, 8, 0, 0, 0, 0, 0, 2, 3, 43, 73, 45, 46, 1, 0, 12, 126, 127,
+                            216, 4, 0, 0, 0
+                        ]))
+                    }
+                )
+                .build()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn create_test_dir_with_archives_and_gzip_content() -> Result<()> {
+        let tar_gz = gzip(&tar_archive(&[
+            ("sub/run.sh", "echo", None),
+            ("run_link", "", Some("sub/run.sh")),
+        ]));
+        let zip = zip_archive(&[("docs/readme.txt", "read me"), ("empty.txt", "")]);
+        let tester = FsTester::from_config(
+            Tree::dir("test_dir_with_archives")
+                .archive("tar", tar_gz)
+                .dir("nested", |d| d.archive("zip", zip))
+                .file(
+                    "log.txt",
+                    FileContent::Gzip {
+                        content: Box::new(FileContent::InlineBytes(gzip(b"compressed log"))),
+                    },
+                )
+                .build(),
+            ".",
+        )?;
+
+        let root = tester.root();
+        assert_eq!(
+            std::fs::read_to_string(root.join("tar/sub/run.sh"))?,
+            "echo"
+        );
+        assert_eq!(
+            std::fs::metadata(root.join("tar/sub/run.sh"))?.mode() & 0o777,
+            0o755
+        );
+        assert_eq!(
+            std::fs::read_link(root.join("tar/run_link"))?,
+            Path::new("sub/run.sh")
+        );
+        assert_eq!(
+            std::fs::read_to_string(root.join("nested/zip/docs/readme.txt"))?,
+            "read me"
+        );
+        assert_eq!(
+            std::fs::read_to_string(root.join("log.txt"))?,
+            "compressed log"
+        );
+        assert!(tester.path("nested/zip/empty.txt")?.is_file());
+        assert!(tester.compare_config(&tester.config)?.is_empty());
+
+        std::fs::write(root.join("tar/sub/run.sh"), "changed")?;
+        assert_eq!(
+            tester.compare_config(&tester.config)?.entries,
+            vec![DiffEntry {
+                path: PathBuf::from("tar/sub/run.sh"),
+                difference: Difference::ContentMismatch {
+                    expected_len: 4,
+                    found_len: 7,
+                    offset: 0
+                },
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn archive_entries_escaping_sandbox_should_be_rejected() {
+        let archives = [
+            zip_archive(&[("../escaped_by_archive.txt", "escaped")]),
+            tar_archive(&[("up", "", Some("../../escaped_by_archive.txt"))]),
+            tar_archive(&[
+                ("inner", "", Some("sub")),
+                ("inner/escaped_by_archive.txt", "escaped", None),
+            ]),
+        ];
+
+        for archive in archives {
+            let result = FsTester::from_config(
+                Tree::dir("test_archive_escape")
+                    .archive("archive", archive)
+                    .build(),
+                ".",
+            );
+
+            assert!(result.is_err_and(|error| error.is_sandbox_escape()
+                && error.entry_path() == Some(Path::new("test_archive_escape/archive"))));
+        }
+        assert!(!Path::new("escaped_by_archive.txt").exists());
+    }
+
+    const YAML_DIR_WITH_TEMPLATE: &str = r#"
+    - !directory
+        name: test_dir_with_template
+        content:
+          - !directory
+              name: data
+              content: []
+          - !file
+              name: config.toml
+              content: !inline_template |
+                data_dir = "{{sandbox_root}}/data"
+                config = "{{ entry_path }}"
+                port = {{port}}
+                literal = "{{{{ not a placeholder }}"
+    "#;
+
+    #[test]
+    fn create_test_dir_with_rendered_template() -> Result<()> {
+        let options = FsTesterOptions {
+            variables: HashMap::from([(String::from("port"), String::from("8080"))]),
+            ..Default::default()
+        };
+        let tester = FsTester::with_options(YAML_DIR_WITH_TEMPLATE, ".", options)?;
+
+        let root = std::fs::canonicalize(tester.root())?;
+        assert_eq!(
+            std::fs::read_to_string(root.join("config.toml"))?,
+            format!(
+                "data_dir = \"{}/data\"\nconfig = \"{}/config.toml\"\nport = 8080\nliteral = \"{{{{ not a placeholder }}}}\"\n",
+                root.display(),
+                root.display()
+            )
+        );
+        assert!(tester.compare(YAML_DIR_WITH_TEMPLATE)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn undefined_template_variable_should_fail_the_build() {
+        let Err(error) = FsTester::new(YAML_DIR_WITH_TEMPLATE, ".") else {
+            panic!("the template variable should not be defined");
+        };
+
+        assert!(error.is_config_format());
+        assert_eq!(
+            error.entry_path(),
+            Some(Path::new("test_dir_with_template/config.toml"))
+        );
+        assert_eq!(error.line(), 9);
+        assert!(error
+            .sandbox_dir()
+            .is_some_and(|sandbox_dir| !Path::new(&sandbox_dir).exists()));
+    }
+
+    #[test]
+    fn create_test_dir_with_modes_and_remove_it() -> Result<()> {
+        let conf = r#"
+        - !directory
+            name: test_dir_with_modes
+            content:
+                - !file
+                    name: script.sh
+                    content:
+                        !inline_text "echo test"
+                    mode: 0o755
+                - !directory
+                    name: locked
+                    mode: 0o500
+                    content:
+                        - !file
+                            name: read_only.txt
+                            content:
+                                !inline_text "test"
+                            mode: 0o444
+                - !clone_directory
+                    name: cloned_src
+                    source: src
+                    mode: 0o555
+        "#;
+
+        let tester = FsTester::new(conf, ".")?;
+        let sandbox_dir = tester.base_dir.clone();
+        tester.perform_fs_test(|dirname| {
+            let dir_path = PathBuf::from(dirname);
+            let mode_of = |path: PathBuf| std::fs::metadata(path).map(|m| m.mode() & 0o7777);
+
+            assert_eq!(mode_of(dir_path.join("script.sh"))?, 0o755);
+            assert_eq!(mode_of(dir_path.join("locked"))?, 0o500);
+            assert_eq!(
+                mode_of(dir_path.join("locked").join("read_only.txt"))?,
+                0o444
+            );
+            assert_eq!(mode_of(dir_path.join("cloned_src"))?, 0o555);
+            Ok(())
+        });
+        drop(tester);
+
+        assert!(std::fs::metadata(sandbox_dir).is_err());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restore_write_permissions_should_unlock_nested_directories() -> Result<()> {
+        let conf = r#"
+        - !directory
+            name: test_restore_write_permissions
+            content:
+                - !directory
+                    name: locked
+                    mode: 0o000
+                    content:
+                        - !directory
+                            name: inner
+                            mode: 0o500
+                            content: []
+        "#;
+
+        let tester = FsTester::new(conf, ".")?;
+        let locked_dir = PathBuf::from(&tester.base_dir).join("locked");
+        FsTester::restore_write_permissions(Path::new(&tester.base_dir))?;
+
+        assert_eq!(std::fs::metadata(&locked_dir)?.mode() & 0o700, 0o700);
+        assert_eq!(
+            std::fs::metadata(locked_dir.join("inner"))?.mode() & 0o700,
+            0o700
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn create_test_dir_with_timestamps() -> Result<()> {
+        use std::time::Duration;
+
+        let conf = r#"
+        - !directory
+            name: test_dir_with_timestamps
+            modified: 2020-01-01T00:00:00Z
+            content:
+                - !file
+                    name: old.txt
+                    content:
+                        !inline_text "test"
+                    modified: 2024-01-31T10:00:00Z
+                    accessed: 2024-02-01T10:00:00Z
+                - !directory
+                    name: inner
+                    modified: -2d
+                    content:
+                        - !file
+                            name: new.txt
+                            content: !empty
+                            mode: 0o000
+                            modified: -1h
+        "#;
+
+        let tester = FsTester::new(conf, ".")?;
+        tester.perform_fs_test(|dirname| {
+            let dir_path = PathBuf::from(dirname);
+            let epoch_secs = |time: SystemTime| {
+                time.duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs()
+            };
+
+            let root = std::fs::metadata(&dir_path)?;
+            assert_eq!(epoch_secs(root.modified()?), 1_577_836_800);
+
+            let old_file = std::fs::metadata(dir_path.join("old.txt"))?;
+            assert_eq!(epoch_secs(old_file.modified()?), 1_706_695_200);
+            assert_eq!(epoch_secs(old_file.accessed()?), 1_706_781_600);
+
+            let two_days_ago = SystemTime::now() - Duration::from_secs(2 * 24 * 3600);
+            let inner = std::fs::metadata(dir_path.join("inner"))?;
+            assert!(epoch_secs(two_days_ago).abs_diff(epoch_secs(inner.modified()?)) < 60);
+
+            let hour_ago = SystemTime::now() - Duration::from_secs(3600);
+            let new_file = std::fs::metadata(dir_path.join("inner").join("new.txt"))?;
+            assert!(epoch_secs(hour_ago).abs_diff(epoch_secs(new_file.modified()?)) < 60);
+            Ok(())
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn clone_directory_should_preserve_timestamps() -> Result<()> {
+        let conf = r#"
+        - !directory
+            name: test_clone_dir_with_preserved_timestamps
+            content:
+                - !clone_directory
+                    name: preserved
+                    source: src
+                    preserve_timestamps: true
+                - !clone_directory
+                    name: copied
+                    source: src
+        "#;
+
+        let tester = FsTester::new(conf, ".")?;
+        tester.perform_fs_test(|dirname| {
+            let dir_path = PathBuf::from(dirname);
+            let source_modified = |path: &str| std::fs::metadata(path)?.modified();
+
+            let preserved_file = std::fs::metadata(dir_path.join("preserved").join("lib.rs"))?;
+            assert_eq!(preserved_file.modified()?, source_modified("src/lib.rs")?);
+
+            let preserved_dir = std::fs::metadata(dir_path.join("preserved").join("rfs"))?;
+            assert_eq!(preserved_dir.modified()?, source_modified("src/rfs")?);
+
+            let copied_file = std::fs::metadata(dir_path.join("copied").join("lib.rs"))?;
+            assert!(copied_file.modified()? > source_modified("src/lib.rs")?);
+            Ok(())
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn compare_should_return_empty_diff_for_matching_sandbox() -> Result<()> {
+        let conf = r#"
+        - !directory
+            name: test_compare_matching_sandbox
+            content:
+                - !file
+                    name: test.txt
+                    content:
+                        !inline_text "test"
+                - !file
+                    name: bytes.bin
+                    content:
+                        !inline_bytes [1, 2, 3]
+                - !directory
+                    name: inner
+                    content:
+                        - !file
+                            name: test_from_cargo.toml
+                            content:
+                                !original_file Cargo.toml
+                        - !symlink
+                            name: test_symlink
+                            target: ../test.txt
+                - !clone_directory
+                    name: cloned_src
+                    source: src
+        "#;
+
+        let tester = FsTester::new(conf, ".")?;
+
+        assert!(tester.compare(conf)?.is_empty());
+        tester.assert_matches(conf);
+        Ok(())
+    }
+
+    #[test]
+    fn compare_should_report_missing_extra_and_differing_entries() -> Result<()> {
+        let conf = r#"
+        - !directory
+            name: test_compare_changed_sandbox
+            content:
+                - !file
+                    name: changed.txt
+                    content:
+                        !inline_text "test"
+                - !file
+                    name: removed.txt
+                    content: !empty
+                - !directory
+                    name: inner
+                    content:
+                        - !symlink
+                            name: test_symlink
+                            target: ../changed.txt
+        "#;
+
+        let tester = FsTester::new(conf, ".")?;
+        tester.perform_fs_test(|dirname| {
+            let dir_path = PathBuf::from(dirname);
+            std::fs::write(dir_path.join("changed.txt"), "tesT!")?;
+            std::fs::remove_file(dir_path.join("removed.txt"))?;
+            std::fs::write(dir_path.join("inner").join("added.txt"), "")?;
+            std::fs::remove_file(dir_path.join("inner").join("test_symlink"))?;
+            std::fs::create_dir(dir_path.join("inner").join("test_symlink"))
+        });
+
+        let diff = tester.compare(conf)?;
+
+        assert_eq!(
+            diff.entries,
+            vec![
+                DiffEntry {
+                    path: PathBuf::from("changed.txt"),
+                    difference: Difference::ContentMismatch {
+                        expected_len: 4,
+                        found_len: 5,
+                        offset: 3,
+                    },
+                },
+                DiffEntry {
+                    path: PathBuf::from("removed.txt"),
+                    difference: Difference::Missing {
+                        kind: EntryKind::File
+                    },
+                },
+                DiffEntry {
+                    path: PathBuf::from("inner/test_symlink"),
+                    difference: Difference::KindMismatch {
+                        expected: EntryKind::Symlink,
+                        found: EntryKind::Directory,
+                    },
+                },
+                DiffEntry {
+                    path: PathBuf::from("inner/added.txt"),
+                    difference: Difference::Extra {
+                        kind: EntryKind::File
+                    },
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "sandbox content does not match the expected configuration")]
+    fn assert_matches_should_panic_with_diff() {
+        let tester =
+            FsTester::new(YAML_DIR_WITH_EMPTY_FILE, ".").expect("Correct config should be here");
+
+        tester.assert_matches(
+            "
+        - !directory
+            name: expected
+            content: []
+        ",
+        );
+    }
+
+    #[test]
+    fn compare_should_reject_config_without_root_directory() -> Result<()> {
+        let tester = FsTester::new(YAML_DIR_WITH_EMPTY_FILE, ".")?;
+
+        let error = tester
+            .compare(YAML_DOUBLE_ROOT_DIRS)
+            .expect_err("double root should be rejected");
+        assert!(error.is_should_start_from_directory());
+        Ok(())
+    }
+
+    #[test]
+    fn create_test_dir_from_built_config() -> Result<()> {
+        let config = Tree::dir("test_from_config")
+            .file("test.txt", "test")
+            .dir("sub", |d| d.file("data.bin", vec![1u8, 2, 3]))
+            .build();
+
+        let tester = FsTester::from_config(config.clone(), ".")?;
+
+        assert_eq!(tester.config, config);
+        assert!(tester.compare_config(&config)?.is_empty());
+        Ok(())
+    }
+
+    const YAML_DIR_FOR_PATH_LOOKUP: &str = r#"
+    - !directory
+        name: test_path_lookup
+        content:
+          - !file
+              name: test.txt
+              content: !empty
+          - !directory
+              name: sub
+              content:
+                - !file
+                    name: file.txt
+                    content: !empty
+                - !symlink
+                    name: test_link
+                    target: ../test.txt
+          - !clone_directory
+              name: copy
+              source: src
+    "#;
+
+    #[test]
+    fn path_should_resolve_declared_paths() -> Result<()> {
+        let tester = FsTester::new(YAML_DIR_FOR_PATH_LOOKUP, ".")?;
+
+        assert_eq!(tester.root(), Path::new(&tester.base_dir));
+        assert_eq!(tester.path("")?, tester.root());
+        assert_eq!(tester.path("test.txt")?, tester.root().join("test.txt"));
+        assert_eq!(
+            tester.path("./sub/../sub/file.txt")?,
+            tester.root().join("sub").join("file.txt")
+        );
+        assert!(tester.path("sub/test_link")?.is_file());
+        assert_eq!(
+            tester.path("copy/lib.rs")?,
+            tester.root().join("copy/lib.rs")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn path_should_reject_paths_outside_of_sandbox() -> Result<()> {
+        let tester = FsTester::new(YAML_DIR_FOR_PATH_LOOKUP, ".")?;
+
+        for path in ["..", "sub/../../test.txt", "/etc/passwd"] {
+            let error = tester.path(path).expect_err("path escapes sandbox");
+            assert!(error.is_sandbox_escape(), "{path}: {error}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn path_should_reject_undeclared_paths() -> Result<()> {
+        let tester = FsTester::new(YAML_DIR_FOR_PATH_LOOKUP, ".")?;
+
+        for path in [
+            "unknown.txt",
+            "sub/unknown.txt",
+            "test.txt/inner",
+            "copy/unknown.rs",
+        ] {
+            let error = tester.path(path).expect_err("path is not declared");
+            assert!(error.is_not_declared(), "{path}: {error}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn perform_fs_test_with_path_should_pass_sandbox_root() -> Result<()> {
+        let tester = FsTester::new(YAML_DIR_FOR_PATH_LOOKUP, ".")?;
+        let expected_root = tester.root().to_path_buf();
+
+        tester.perform_fs_test_with_path(|root| {
+            assert_eq!(root, expected_root);
+            assert!(root.join("sub").join("file.txt").is_file());
+            Ok(())
+        });
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn perform_fs_test_with_path_async_should_pass_sandbox_root() -> Result<()> {
+        let tester = FsTester::new_async(YAML_DIR_FOR_PATH_LOOKUP, ".").await?;
+
+        tester
+            .perform_fs_test_with_path_async(async |root| {
+                let metadata = fs::metadata(root.join("test.txt")).await?;
+                assert!(metadata.is_file());
+                Ok(())
+            })
+            .await;
+        Ok(())
+    }
+
+    #[test]
+    fn try_perform_fs_test_should_return_result_and_remove_sandbox() -> Result<()> {
+        let tester = FsTester::new(YAML_DIR_FOR_PATH_LOOKUP, ".")?;
+        let root = tester.root().to_path_buf();
+
+        let mut visited = vec![];
+        let names = tester.try_perform_fs_test(|root| {
+            for entry in std::fs::read_dir(root)? {
+                visited.push(entry?.file_name());
+            }
+            Ok::<_, io::Error>(visited.len())
+        })?;
+
+        assert_eq!(names, 3);
+        assert!(!root.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn try_perform_fs_test_should_return_closure_error_and_remove_sandbox() -> Result<()> {
+        let tester = FsTester::new(YAML_DIR_FOR_PATH_LOOKUP, ".")?;
+        let root = tester.root().to_path_buf();
+
+        let result: std::result::Result<(), String> =
+            tester.try_perform_fs_test(|_| Err(String::from("broken")));
+
+        assert_eq!(result, Err(String::from("broken")));
+        assert!(!root.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn try_perform_fs_test_should_keep_sandbox_on_error_with_keep_on_failure() -> Result<()> {
+        let options = FsTesterOptions {
+            keep_on_failure: true,
+            ..Default::default()
+        };
+        let tester = FsTester::with_options(YAML_DIR_FOR_PATH_LOOKUP, ".", options)?;
+        let root = tester.root().to_path_buf();
+
+        let result = tester.try_perform_fs_test(|_| Err::<(), _>("broken"));
+
+        let kept = root.is_dir();
+        std::fs::remove_dir_all(&root)?;
+        assert!(result.is_err());
+        assert!(kept);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn try_perform_fs_test_async_should_return_result() -> Result<()> {
+        let tester = FsTester::new_async(YAML_DIR_FOR_PATH_LOOKUP, ".").await?;
+        let root = tester.root().to_path_buf();
+
+        let content = tester
+            .try_perform_fs_test_async(async |root| fs::read(root.join("test.txt")).await)
+            .await?;
+
+        assert!(content.is_empty());
+        assert!(!root.exists());
+        Ok(())
+    }
+
+    const YAML_DIR_FOR_CLONE_FILTERS: &str = r#"
+    - !directory
+        name: test_clone_filters_source
+        content:
+          - !file
+              name: .gitignore
+              content: !inline_text "*.log\n"
+          - !file
+              name: main.rs
+              content: !inline_text "fn main() {}"
+          - !file
+              name: debug.log
+              content: !empty
+          - !directory
+              name: target
+              content:
+                - !file
+                    name: main.rs
+                    content: !empty
+          - !directory
+              name: docs
+              content:
+                - !file
+                    name: readme.md
+                    content: !empty
+          - !directory
+              name: nested
+              content:
+                - !directory
+                    name: deep
+                    content:
+                      - !file
+                          name: lib.rs
+                          content: !empty
+    "#;
+
+    fn clone_with_filters(source: &FsTester, conf: CloneDirectoryConf) -> Result<FsTester> {
+        FsTester::from_config(
+            Tree::dir("test_clone_filters")
+                .entry(CloneDirectoryConf {
+                    name: String::from("copy"),
+                    source: source.base_dir.clone(),
+                    ..conf
+                })
+                .build(),
+            ".",
+        )
+    }
+
+    #[test]
+    fn clone_directory_should_skip_excluded_and_gitignored_entries() -> Result<()> {
+        let source = FsTester::new(YAML_DIR_FOR_CLONE_FILTERS, ".")?;
+
+        let tester = clone_with_filters(
+            &source,
+            CloneDirectoryConf {
+                exclude: vec![String::from("/target/")],
+                respect_gitignore: true,
+                ..Default::default()
+            },
+        )?;
+
+        tester.assert_matches(
+            r#"
+            - !directory
+                name: expected
+                content:
+                  - !directory
+                      name: copy
+                      content:
+                        - !file
+                            name: .gitignore
+                            content: !inline_text "*.log\n"
+                        - !file
+                            name: main.rs
+                            content: !inline_text "fn main() {}"
+                        - !directory
+                            name: docs
+                            content:
+                              - !file
+                                  name: readme.md
+                                  content: !empty
+                        - !directory
+                            name: nested
+                            content:
+                              - !directory
+                                  name: deep
+                                  content:
+                                    - !file
+                                        name: lib.rs
+                                        content: !empty
+            "#,
+        );
+        assert!(tester.compare_config(&tester.config)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn clone_directory_should_copy_only_included_entries_and_prune_empty_dirs() -> Result<()> {
+        let source = FsTester::new(YAML_DIR_FOR_CLONE_FILTERS, ".")?;
+
+        let tester = clone_with_filters(
+            &source,
+            CloneDirectoryConf {
+                include: vec![String::from("*.rs"), String::from("docs/")],
+                exclude: vec![String::from("target/")],
+                ..Default::default()
+            },
+        )?;
+
+        tester.assert_matches(
+            r#"
+            - !directory
+                name: expected
+                content:
+                  - !directory
+                      name: copy
+                      content:
+                        - !file
+                            name: main.rs
+                            content: !inline_text "fn main() {}"
+                        - !directory
+                            name: docs
+                            content:
+                              - !file
+                                  name: readme.md
+                                  content: !empty
+                        - !directory
+                            name: nested
+                            content:
+                              - !directory
+                                  name: deep
+                                  content:
+                                    - !file
+                                        name: lib.rs
+                                        content: !empty
+            "#,
+        );
+        assert!(tester.compare_config(&tester.config)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn clone_directory_should_reject_invalid_pattern() -> Result<()> {
+        let source = FsTester::new(YAML_DIR_FOR_CLONE_FILTERS, ".")?;
+
+        let result = clone_with_filters(
+            &source,
+            CloneDirectoryConf {
+                include: vec![String::from("[z-a")],
+                ..Default::default()
+            },
+        );
+
+        assert!(result.is_err_and(|error| matches!(
+            error.config_issues(),
+            [ConfigIssue {
+                kind: IssueKind::InvalidPattern(_),
+                ..
+            }]
+        )));
+        Ok(())
+    }
+
+    const YAML_DIR_FOR_EXACT_CLONE: &str = "
+    - !directory
+        name: test_exact_clone_source
+        content:
+          - !file
+              name: script.sh
+              content: !inline_text \"echo test\"
+              mode: 0o750
+          - !symlink
+              name: script_link
+              target: script.sh
+          - !directory
+              name: data
+              mode: 0o700
+              content:
+                - !symlink
+                    name: dangling
+                    target: ../missing.txt
+    ";
+
+    #[cfg(unix)]
+    #[test]
+    fn clone_directory_with_exact_fidelity_should_keep_symlinks_and_modes() -> Result<()> {
+        use crate::rfs::config::clone_directory_conf::CloneFidelity;
+
+        let source = FsTester::new(YAML_DIR_FOR_EXACT_CLONE, ".")?;
+        let tester = clone_with_filters(
+            &source,
+            CloneDirectoryConf {
+                fidelity: CloneFidelity::Exact,
+                ..Default::default()
+            },
+        )?;
+
+        tester.perform_fs_test(|dirname| {
+            let copy_path = PathBuf::from(dirname).join("copy");
+            let mode_of = |path: PathBuf| std::fs::metadata(path).map(|m| m.mode() & 0o7777);
+
+            assert_eq!(
+                std::fs::read_link(copy_path.join("script_link"))?,
+                PathBuf::from("script.sh")
+            );
+            assert_eq!(
+                std::fs::read_link(copy_path.join("data").join("dangling"))?,
+                PathBuf::from("../missing.txt")
+            );
+            assert_eq!(mode_of(copy_path.join("script.sh"))?, 0o750);
+            assert_eq!(mode_of(copy_path.join("data"))?, 0o700);
+            assert_eq!(
+                std::fs::metadata(copy_path.join("script.sh"))?.modified()?,
+                std::fs::metadata(source.root().join("script.sh"))?.modified()?
+            );
+            Ok(())
+        });
+        assert!(tester.compare_config(&tester.config)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn clone_directory_with_content_fidelity_should_skip_symlinks() -> Result<()> {
+        let source = FsTester::new(YAML_DIR_FOR_EXACT_CLONE, ".")?;
+        let tester = clone_with_filters(&source, CloneDirectoryConf::default())?;
+
+        tester.perform_fs_test(|dirname| {
+            let copy_path = PathBuf::from(dirname).join("copy");
+
+            assert!(copy_path.join("script.sh").is_file());
+            assert!(std::fs::symlink_metadata(copy_path.join("script_link")).is_err());
+            assert!(std::fs::symlink_metadata(copy_path.join("data").join("dangling")).is_err());
+            Ok(())
+        });
+        assert!(tester.compare_config(&tester.config)?.is_empty());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn clone_directory_with_exact_fidelity_should_report_or_skip_special_files() -> Result<()> {
+        use crate::rfs::config::clone_directory_conf::CloneFidelity;
+        use std::os::unix::net::UnixListener;
+
+        let source = FsTester::new(YAML_DIR_FOR_EXACT_CLONE, ".")?;
+        let socket_path = source.root().join("data").join("test.sock");
+        let _listener = UnixListener::bind(&socket_path)?;
+
+        let result = clone_with_filters(
+            &source,
+            CloneDirectoryConf {
+                fidelity: CloneFidelity::Exact,
+                ..Default::default()
+            },
+        );
+        assert!(result.is_err_and(|error| error.is_special_file()));
+
+        let tester = clone_with_filters(
+            &source,
+            CloneDirectoryConf {
+                fidelity: CloneFidelity::Exact,
+                skip_special_files: true,
+                ..Default::default()
+            },
+        )?;
+        tester.perform_fs_test(|dirname| {
+            let copy_path = PathBuf::from(dirname).join("copy");
+
+            assert!(copy_path.join("script.sh").is_file());
+            assert!(std::fs::symlink_metadata(copy_path.join("data").join("test.sock")).is_err());
+            Ok(())
+        });
+        assert!(tester.compare_config(&tester.config)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn clone_directory_should_apply_removal_and_overlay_content() -> Result<()> {
+        let source = FsTester::new(YAML_DIR_FOR_CLONE_FILTERS, ".")?;
+
+        let tester = clone_with_filters(
+            &source,
+            CloneDirectoryConf {
+                exclude: vec![String::from("/target/")],
+                respect_gitignore: true,
+                remove: vec![String::from("docs"), String::from("nested/deep/lib.rs")],
+                content: Tree::dir("overlay")
+                    .file("main.rs", "fn main() { overlay() }")
+                    .file("docs", "replaced directory")
+                    .dir("nested", |d| d.file("new.rs", "").dir("deep", |d| d))
+                    .into_conf()
+                    .content,
+                ..Default::default()
+            },
+        )?;
+
+        tester.assert_matches(
+            r#"
+            - !directory
+                name: expected
+                content:
+                  - !directory
+                      name: copy
+                      content:
+                        - !file
+                            name: .gitignore
+                            content: !inline_text "*.log\n"
+                        - !file
+                            name: main.rs
+                            content: !inline_text "fn main() { overlay() }"
+                        - !file
+                            name: docs
+                            content: !inline_text "replaced directory"
+                        - !directory
+                            name: nested
+                            content:
+                              - !file
+                                  name: new.rs
+                                  content: !empty
+                              - !directory
+                                  name: deep
+                                  content: []
+            "#,
+        );
+        assert!(tester.compare_config(&tester.config)?.is_empty());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn clone_directory_with_exact_fidelity_should_apply_read_only_mode_after_overlay(
+    ) -> Result<()> {
+        use crate::rfs::config::clone_directory_conf::CloneFidelity;
+
+        let source = FsTester::from_config(
+            Tree::dir("test_clone_read_only_source")
+                .entry(DirectoryConf {
+                    name: String::from("ro"),
+                    mode: Some(0o555),
+                    content: Tree::dir("ro").file("old.txt", "old").into_conf().content,
+                    ..Default::default()
+                })
+                .build(),
+            ".",
+        )?;
+        let tester = clone_with_filters(
+            &source,
+            CloneDirectoryConf {
+                fidelity: CloneFidelity::Exact,
+                content: Tree::dir("overlay")
+                    .dir("ro", |d| d.file("new.txt", "new"))
+                    .into_conf()
+                    .content,
+                ..Default::default()
+            },
+        )?;
+
+        tester.perform_fs_test(|dirname| {
+            let ro_path = PathBuf::from(dirname).join("copy").join("ro");
+
+            assert_eq!(std::fs::read_to_string(ro_path.join("old.txt"))?, "old");
+            assert_eq!(std::fs::read_to_string(ro_path.join("new.txt"))?, "new");
+            assert_eq!(std::fs::metadata(&ro_path)?.mode() & 0o7777, 0o555);
+            Ok(())
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn clone_directory_overlay_should_replace_symlinks_instead_of_writing_through_them(
+    ) -> Result<()> {
+        use crate::rfs::config::clone_directory_conf::CloneFidelity;
+
+        let source = FsTester::new(YAML_DIR_FOR_EXACT_CLONE, ".")?;
+        let tester = clone_with_filters(
+            &source,
+            CloneDirectoryConf {
+                fidelity: CloneFidelity::Exact,
+                content: Tree::dir("overlay")
+                    .file("script_link", "echo overlay")
+                    .into_conf()
+                    .content,
+                ..Default::default()
+            },
+        )?;
+
+        tester.perform_fs_test(|dirname| {
+            let copy_path = PathBuf::from(dirname).join("copy");
+            let link_metadata = std::fs::symlink_metadata(copy_path.join("script_link"))?;
+
+            assert!(link_metadata.is_file());
+            assert_eq!(
+                std::fs::read_to_string(copy_path.join("script.sh"))?,
+                "echo test"
+            );
+            Ok(())
+        });
+        assert!(tester.compare_config(&tester.config)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn clone_directory_should_reject_cloned_symlinks_outside_of_sandbox_without_opt_in(
+    ) -> Result<()> {
+        use crate::rfs::config::clone_directory_conf::CloneFidelity;
+
+        let outside_target = std::fs::canonicalize("Cargo.toml")?;
+        let source = FsTester::from_config(
+            Tree::dir("test_clone_outside_symlink_source")
+                .file("test.txt", "test")
+                .entry(SymlinkConf {
+                    name: String::from("escape"),
+                    target: outside_target.to_string_lossy().into_owned(),
+                    allow_outside: true,
+                })
+                .build(),
+            ".",
+        )?;
+
+        let result = clone_with_filters(
+            &source,
+            CloneDirectoryConf {
+                fidelity: CloneFidelity::Exact,
+                ..Default::default()
+            },
+        );
+        assert!(result.is_err_and(|error| error.is_sandbox_escape()));
+
+        let tester = clone_with_filters(
+            &source,
+            CloneDirectoryConf {
+                fidelity: CloneFidelity::Exact,
+                allow_outside: true,
+                ..Default::default()
+            },
+        )?;
+        tester.perform_fs_test(|dirname| {
+            let copy_path = PathBuf::from(dirname).join("copy");
+
+            assert_eq!(std::fs::read_link(copy_path.join("escape"))?, outside_target);
+            Ok(())
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn clone_directory_should_not_remove_through_cloned_symlinks() -> Result<()> {
+        use crate::rfs::config::clone_directory_conf::CloneFidelity;
+
+        let source = FsTester::from_config(
+            Tree::dir("test_remove_through_symlink_source")
+                .file("test.txt", "test")
+                .entry(SymlinkConf {
+                    name: String::from("outside"),
+                    target: std::fs::canonicalize(".")?.to_string_lossy().into_owned(),
+                    allow_outside: true,
+                })
+                .build(),
+            ".",
+        )?;
+        let target_path = source.root().join("test.txt");
+
+        let result = clone_with_filters(
+            &source,
+            CloneDirectoryConf {
+                fidelity: CloneFidelity::Exact,
+                allow_outside: true,
+                remove: vec![format!(
+                    "outside/{}/test.txt",
+                    source
+                        .root()
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                )],
+                ..Default::default()
+            },
+        );
+
+        assert!(result.is_err_and(|error| error.is_sandbox_escape()));
+        assert!(target_path.is_file());
+        Ok(())
+    }
+
+    #[test]
+    fn clone_directory_should_reject_removal_outside_of_clone() -> Result<()> {
+        let source = FsTester::new(YAML_DIR_FOR_CLONE_FILTERS, ".")?;
+
+        let result = clone_with_filters(
+            &source,
+            CloneDirectoryConf {
+                remove: vec![String::from("../copy")],
+                ..Default::default()
+            },
+        );
+        assert!(result.is_err_and(|error| matches!(
+            error.config_issues(),
+            [ConfigIssue {
+                kind: IssueKind::InvalidRemovePath(_),
+                ..
+            }]
+        )));
+
+        let result = clone_with_filters(
+            &source,
+            CloneDirectoryConf {
+                remove: vec![String::from("missing.txt")],
+                ..Default::default()
+            },
+        );
+        assert!(result.is_err_and(|error| error.io_error_kind() == Some(io::ErrorKind::NotFound)));
+        Ok(())
+    }
+
+    #[test]
+    fn sandbox_should_be_named_after_the_test() -> Result<()> {
+        let tester = FsTester::with_options(
+            YAML_DIR_WITH_EMPTY_FILE,
+            ".",
+            FsTesterOptions {
+                naming: SandboxNaming::TestName,
+                ..Default::default()
+            },
+        )?;
+
+        let expected_suffix = "rfs_fs_tester_tests_sandbox_should_be_named_after_the_test";
+        let sandbox_name = tester.root().file_name().unwrap_or_default();
+        assert!(sandbox_name.to_string_lossy().ends_with(expected_suffix));
+        Ok(())
+    }
+
+    #[test]
+    fn sandbox_with_exact_name_should_not_replace_existing_directory() -> Result<()> {
+        let options = FsTesterOptions {
+            naming: SandboxNaming::Exact(String::from("test_exact_sandbox_name")),
+            ..Default::default()
+        };
+        let tester = FsTester::with_options(YAML_DIR_WITH_EMPTY_FILE, ".", options.clone())?;
+
+        let result = FsTester::with_options(YAML_DIR_WITH_EMPTY_FILE, ".", options);
+
+        assert!(result.is_err_and(|error| error.is_sandbox_exists()));
+        assert_eq!(
+            tester.root(),
+            Path::new(".").join("test_exact_sandbox_name")
+        );
+        assert!(tester.root().join("test.txt").is_file());
+        Ok(())
+    }
+
+    fn keep_on_failure_options() -> FsTesterOptions {
+        FsTesterOptions {
+            keep_on_failure: true,
+            write_manifest: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sandbox_should_be_kept_when_test_closure_returns_error() {
+        let tester =
+            FsTester::with_options(YAML_DIR_WITH_EMPTY_FILE, ".", keep_on_failure_options())
+                .expect("Correct config should be here");
+        let sandbox_dir = tester.base_dir.clone();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            tester.perform_fs_test(|_| Err(io::Error::other("broken")));
+        }));
+        drop(tester);
+
+        assert!(result.is_err());
+        assert!(Path::new(&sandbox_dir).join("test.txt").is_file());
+        let manifest_path = format!("{}.manifest.yaml", sandbox_dir);
+        let manifest = std::fs::read_to_string(&manifest_path).unwrap_or_default();
+
+        std::fs::remove_dir_all(&sandbox_dir).expect("sandbox should be removable");
+        std::fs::remove_file(&manifest_path).expect("manifest should be removable");
+        assert!(manifest.contains("inner test has error: broken"));
+        assert!(manifest.contains("test.txt"));
+    }
+
+    #[test]
+    fn sandbox_should_be_kept_when_test_closure_panics() {
+        let options = FsTesterOptions {
+            keep_on_failure: true,
+            ..Default::default()
+        };
+        let tester = FsTester::with_options(YAML_DIR_WITH_EMPTY_FILE, ".", options)
+            .expect("Correct config should be here");
+        let sandbox_dir = tester.base_dir.clone();
+
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            tester.perform_fs_test(|_| panic!("assertion failed"));
+        }));
+        drop(tester);
+
+        assert!(result.is_err());
+        let kept = Path::new(&sandbox_dir).is_dir();
+        let has_manifest = Path::new(&format!("{}.manifest.yaml", sandbox_dir)).exists();
+        std::fs::remove_dir_all(&sandbox_dir).expect("sandbox should be removable");
+        assert!(kept);
+        assert!(!has_manifest);
+    }
+
+    #[test]
+    fn sandbox_should_be_removed_after_successful_test_with_keep_on_failure() {
+        let tester =
+            FsTester::with_options(YAML_DIR_WITH_EMPTY_FILE, ".", keep_on_failure_options())
+                .expect("Correct config should be here");
+        let sandbox_dir = tester.base_dir.clone();
+
+        tester.perform_fs_test(|_| Ok(()));
+        drop(tester);
+
+        assert!(!Path::new(&sandbox_dir).exists());
+        assert!(!Path::new(&format!("{}.manifest.yaml", sandbox_dir)).exists());
+    }
+
     #[test]
     fn yaml_config_serialization_explorer() {
         let test_conf = Configuration(vec![ConfigEntry::Directory(DirectoryConf {
@@ -1080,7 +4180,9 @@ mod tests {
             content: vec![ConfigEntry::File(FileConf {
                 name: String::from("test.txt"),
                 content: FileContent::OriginalFile(String::from("Cargo.toml")),
+                ..Default::default()
             })],
+            ..Default::default()
         })]);
 
         let config = serde_yaml::to_string(&test_conf).unwrap();
@@ -1100,7 +4202,9 @@ mod tests {
             content: vec![ConfigEntry::File(FileConf {
                 name: String::from("test.txt"),
                 content: FileContent::OriginalFile(String::from("Cargo.toml")),
+                ..Default::default()
             })],
+            ..Default::default()
         })]);
 
         let config = serde_json::to_string(&test_conf).unwrap();
@@ -2,11 +2,15 @@
 use std::fmt::{Debug, Display};
 use std::io::Error as IoError;
 use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
 use std::{fmt, result as std_result};
 
 use tokio::sync::AcquireError;
 use tokio::task::JoinError;
 
+use super::config::location::locate_entry;
+use super::config::{ConfigEntry, ConfigIssue};
+
 /// This type represents configuration parse and test directory creation errors
 pub struct FsTesterError {
     err: Box<ErrorImpl>,
@@ -25,6 +29,7 @@ macro_rules! fs_tester_error {
                 code: $code,
                 line: $line,
                 column: $column,
+                entry: None,
             }),
             sandbox_dir: $sandbox_dir,
         }
@@ -48,11 +53,52 @@ impl FsTesterError {
         fs_tester_error!(ErrorCode::ShouldStartFromDirectory)
     }
 
+    /// Construct error instance when the configuration validation found problems.
+    pub fn invalid_config(issues: Vec<ConfigIssue>) -> Self {
+        fs_tester_error!(ErrorCode::InvalidConfig(issues))
+    }
+
     /// If any non-allowed settings are found in the configuration, an error instance will be created.
     pub fn not_allowed_settings() -> Self {
         fs_tester_error!(ErrorCode::LinksNotAllowed)
     }
 
+    /// Construct error instance when the path leads outside of the sandbox directory.
+    pub fn path_escapes_sandbox(path: &Path) -> Self {
+        fs_tester_error!(ErrorCode::PathEscapesSandbox(path.to_path_buf()))
+    }
+
+    /// Construct error instance when the path is not declared in the configuration.
+    pub fn path_not_declared(path: &Path) -> Self {
+        fs_tester_error!(ErrorCode::PathNotDeclared(path.to_path_buf()))
+    }
+
+    /// Construct error instance when the special file (FIFO, socket, device) can not be cloned.
+    pub fn special_file(path: &Path) -> Self {
+        fs_tester_error!(ErrorCode::SpecialFile(path.to_path_buf()))
+    }
+
+    /// Construct error instance when the sandbox directory with the chosen name already exists.
+    pub fn sandbox_exists(path: &Path) -> Self {
+        fs_tester_error!(ErrorCode::SandboxExists(path.to_path_buf()))
+    }
+
+    /// Construct error instance when the template of the file content can not be rendered.
+    pub fn invalid_template(problem: impl Into<String>) -> Self {
+        fs_tester_error!(ErrorCode::InvalidTemplate(problem.into()))
+    }
+
+    /// Construct error instance when several entries failed to be built in the collecting mode.
+    pub(crate) fn build_failed(errors: Vec<FsTesterError>) -> Self {
+        fs_tester_error!(ErrorCode::BuildFailed(errors))
+    }
+
+    /// Construct error instance when the entry was not built, because the build
+    /// was cancelled after the failure of another entry.
+    pub(crate) fn cancelled() -> Self {
+        fs_tester_error!(ErrorCode::Cancelled)
+    }
+
     /// An error instance is created when an input/output error occurs.
     pub fn io_error(err: std::io::Error) -> Self {
         fs_tester_error!(ErrorCode::Io(err))
@@ -64,15 +110,113 @@ impl FsTesterError {
     }
 
     /// One-based line at which the error was detected.
+    ///
+    /// The build errors of a parsed configuration get the approximate line of the failed
+    /// entry: it is found by searching the `name` keys in the text, so it may point to
+    /// the wrong place if the same `name: <value>` text appears inside an inline content.
     pub fn line(&self) -> usize {
         self.err.line
     }
 
-    /// One-based column number at witch the error was detected
+    /// One-based column number at witch the error was detected,
+    /// as approximate as the [`FsTesterError::line`] of the failed entry.
     pub fn column(&self) -> usize {
         self.err.column
     }
 
+    /// The path of the configuration entry which failed to be built,
+    /// starting from the root entry name, e.g. `root/sub/test.mp3`.
+    pub fn entry_path(&self) -> Option<&Path> {
+        self.err.entry.as_ref().map(|entry| entry.path.as_path())
+    }
+
+    /// The kind of the configuration entry which failed to be built, e.g. `file`.
+    pub fn entry_kind(&self) -> Option<&'static str> {
+        self.err.entry.as_ref().map(|entry| entry.kind)
+    }
+
+    /// The source path of the configuration entry which failed to be built:
+    /// the source of the cloned directory, the original file or the link target.
+    pub fn entry_source(&self) -> Option<&Path> {
+        self.err
+            .entry
+            .as_ref()
+            .and_then(|entry| entry.source.as_deref())
+    }
+
+    /// Attaches the configuration entry to the error raised while the entry was built.
+    /// If the error already names a nested entry, the entry name is prepended to its path.
+    pub(crate) fn within_entry(mut self, entry: &ConfigEntry) -> Self {
+        if let ErrorCode::BuildFailed(errors) = &mut self.err.code {
+            *errors = std::mem::take(errors)
+                .into_iter()
+                .map(|error| error.within_entry(entry))
+                .collect();
+            return self;
+        }
+
+        match &mut self.err.entry {
+            Some(context) => context.path = Path::new(entry.name()).join(&context.path),
+            None => {
+                self.err.entry = Some(EntryContext {
+                    path: PathBuf::from(entry.name()),
+                    kind: entry.kind(),
+                    source: entry.source().map(PathBuf::from),
+                })
+            }
+        }
+        self
+    }
+
+    /// Sets the approximate line and column of the failed entry found in the configuration
+    /// string, unless the error already has its location.
+    pub(crate) fn locate_entry_in(mut self, config_str: &str) -> Self {
+        if let ErrorCode::BuildFailed(errors) = &mut self.err.code {
+            *errors = std::mem::take(errors)
+                .into_iter()
+                .map(|error| error.locate_entry_in(config_str))
+                .collect();
+        } else if self.err.line == 0 {
+            let location = self
+                .err
+                .entry
+                .as_ref()
+                .and_then(|entry| locate_entry(config_str, &entry.path));
+            if let Some((line, column)) = location {
+                self.err.line = line;
+                self.err.column = column;
+            }
+        }
+        self
+    }
+
+    /// The errors of all failed entries, if the sandbox was built in the collecting mode
+    /// and more than one entry failed. Otherwise the error describes the only failed entry
+    /// and the list is empty.
+    pub fn build_errors(&self) -> &[FsTesterError] {
+        match &self.err.code {
+            ErrorCode::BuildFailed(errors) => errors,
+            _ => &[],
+        }
+    }
+
+    /// Splits the aggregated error into the errors of the failed entries.
+    pub(crate) fn into_build_errors(self) -> Vec<FsTesterError> {
+        match self.err.code {
+            ErrorCode::BuildFailed(errors) => errors,
+            _ => vec![self],
+        }
+    }
+
+    /// Returns true if the entry was not built because the build was cancelled
+    /// after the failure of another entry.
+    pub(crate) fn is_cancelled(&self) -> bool {
+        matches!(
+            self.err.code,
+            ErrorCode::Cancelled | ErrorCode::AcquireError(_)
+        )
+    }
+
     /// The sandbox_dir getter
     pub fn sandbox_dir(&self) -> Option<String> {
         self.sandbox_dir.clone()
@@ -89,13 +233,38 @@ impl FsTesterError {
     /// - `Category::NotAllowedSettings` - used not activated configuration features
     /// - `Category::Syntax` - Json or Yaml parsers are encountered error when parsed config
     /// - `Category::Io` - failure to read or write data
+    /// - `Category::SandboxEscape` - the path leads outside of the sandbox directory,
+    ///   or the configuration has entries which would be created outside of it
+    /// - `Category::NotDeclared` - the path is not declared in the configuration
+    ///
+    /// The errors of several failed entries have the category of the first one.
     pub fn classify(&self) -> Category {
         match self.err.code {
-            ErrorCode::EmptyConfig | ErrorCode::ShouldStartFromDirectory => Category::ConfigFormat,
+            ErrorCode::BuildFailed(ref errors) => errors
+                .first()
+                .map_or(Category::Io, |error| error.classify()),
+            ErrorCode::InvalidConfig(ref issues)
+                if issues.iter().any(|issue| issue.kind.is_sandbox_escape()) =>
+            {
+                Category::SandboxEscape
+            }
+            ErrorCode::EmptyConfig
+            | ErrorCode::ShouldStartFromDirectory
+            | ErrorCode::InvalidConfig(_)
+            | ErrorCode::InvalidTemplate(_) => Category::ConfigFormat,
             ErrorCode::LinksNotAllowed => Category::NotAllowedSettings,
-            ErrorCode::JsonSyntax(_) | ErrorCode::YamlSyntax(_) => Category::Syntax,
-            ErrorCode::Io(_) | ErrorCode::WalkDir(_) => Category::Io,
-            ErrorCode::AcquireError(_) | ErrorCode::JoinError(_) => Category::Multitasking,
+            ErrorCode::JsonSyntax(_) | ErrorCode::YamlSyntax(_) | ErrorCode::GlobPattern(_) => {
+                Category::Syntax
+            }
+            ErrorCode::Io(_)
+            | ErrorCode::WalkDir(_)
+            | ErrorCode::SpecialFile(_)
+            | ErrorCode::SandboxExists(_) => Category::Io,
+            ErrorCode::AcquireError(_) | ErrorCode::JoinError(_) | ErrorCode::Cancelled => {
+                Category::Multitasking
+            }
+            ErrorCode::PathEscapesSandbox(_) => Category::SandboxEscape,
+            ErrorCode::PathNotDeclared(_) => Category::NotDeclared,
         }
     }
 
@@ -123,6 +292,16 @@ impl FsTesterError {
         self.classify() == Category::NotAllowedSettings
     }
 
+    /// Returns true if this error was caused by the path outside of the sandbox directory
+    pub fn is_sandbox_escape(&self) -> bool {
+        self.classify() == Category::SandboxEscape
+    }
+
+    /// Returns true if this error was caused by the path missing in the configuration
+    pub fn is_not_declared(&self) -> bool {
+        self.classify() == Category::NotDeclared
+    }
+
     pub fn io_error_kind(&self) -> Option<ErrorKind> {
         if let ErrorCode::Io(io_error) = &self.err.code {
             Some(io_error.kind())
@@ -131,6 +310,22 @@ impl FsTesterError {
         }
     }
 
+    pub fn is_special_file(&self) -> bool {
+        matches!(self.err.code, ErrorCode::SpecialFile(_))
+    }
+
+    pub fn is_sandbox_exists(&self) -> bool {
+        matches!(self.err.code, ErrorCode::SandboxExists(_))
+    }
+
+    /// The problems found by the configuration validation.
+    pub fn config_issues(&self) -> &[ConfigIssue] {
+        match &self.err.code {
+            ErrorCode::InvalidConfig(issues) => issues,
+            _ => &[],
+        }
+    }
+
     pub fn is_empty_config(&self) -> bool {
         matches!(self.err.code, ErrorCode::EmptyConfig)
     }
@@ -140,7 +335,10 @@ impl FsTesterError {
     }
 }
 
+/// The category of the error. New categories can be added in minor versions,
+/// so the matches on it should have a wildcard arm.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum Category {
     /// The error was caused by a failure configuration format.
     ConfigFormat,
@@ -157,6 +355,12 @@ pub enum Category {
 
     /// The error was caused by the failure of multitasking.
     Multitasking,
+
+    /// The path leads outside of the sandbox directory.
+    SandboxEscape,
+
+    /// The path is not declared in the configuration.
+    NotDeclared,
 }
 
 #[derive(Debug)]
@@ -167,6 +371,15 @@ pub(crate) enum ErrorCode {
     /// The configuration should start from the containing directory.
     ShouldStartFromDirectory,
 
+    /// The configuration validation found problems.
+    InvalidConfig(Vec<ConfigIssue>),
+
+    /// The template of the file content refers to an unknown variable or is not closed.
+    InvalidTemplate(String),
+
+    /// Several entries failed to be built in the collecting mode.
+    BuildFailed(Vec<FsTesterError>),
+
     /// If user not set LINKS_ALLOWED env variable and configuration
     /// has links entries notify this error
     LinksNotAllowed,
@@ -177,17 +390,35 @@ pub(crate) enum ErrorCode {
     /// Json parser encountered error.
     JsonSyntax(serde_json::Error),
 
+    /// The include or exclude pattern of the cloned directory or a .gitignore file is invalid.
+    GlobPattern(ignore::Error),
+
     /// Some Walkdir error occurred while walking thru directory entry hierarchy
     WalkDir(walkdir::Error),
 
     /// Some I/O error occurred while serializing or deserializing.
     Io(std::io::Error),
 
+    /// The special file of the cloned directory can not be copied.
+    SpecialFile(PathBuf),
+
+    /// The sandbox directory with the exact or test-derived name already exists.
+    SandboxExists(PathBuf),
+
+    /// The build was cancelled after the failure of another entry.
+    Cancelled,
+
     /// An error occurred while attempting to acquire a semaphore.
     AcquireError(AcquireError),
 
     /// An error occurred while trying to work with the joined task handle.
     JoinError(JoinError),
+
+    /// The looked up path leads outside of the sandbox directory.
+    PathEscapesSandbox(PathBuf),
+
+    /// The looked up path is not declared in the configuration.
+    PathNotDeclared(PathBuf),
 }
 
 #[derive(Debug)]
@@ -195,6 +426,15 @@ struct ErrorImpl {
     code: ErrorCode,
     line: usize,
     column: usize,
+    entry: Option<EntryContext>,
+}
+
+/// The configuration entry which failed to be built.
+#[derive(Debug)]
+struct EntryContext {
+    path: PathBuf,
+    kind: &'static str,
+    source: Option<PathBuf>,
 }
 
 impl Display for ErrorCode {
@@ -209,6 +449,23 @@ impl Display for ErrorCode {
                     "The configuration should start from the containing directory."
                 )
             }
+            ErrorCode::InvalidConfig(issues) => {
+                write!(f, "The configuration is invalid:")?;
+                for issue in issues {
+                    write!(f, "\n  {}", issue)?;
+                }
+                Ok(())
+            }
+            ErrorCode::InvalidTemplate(problem) => {
+                write!(f, "The template is invalid: {}.", problem)
+            }
+            ErrorCode::BuildFailed(errors) => {
+                write!(f, "{} entries failed to be built:", errors.len())?;
+                for error in errors {
+                    write!(f, "\n  {}", error)?;
+                }
+                Ok(())
+            }
             ErrorCode::LinksNotAllowed => {
                 write!(
                     f,
@@ -222,10 +479,31 @@ impl Display for ErrorCode {
             }
             ErrorCode::WalkDir(err) => write!(f, "Walkdir error: {}", err),
             ErrorCode::Io(err) => write!(f, "IO error: {}", err),
+            ErrorCode::SpecialFile(path) => {
+                write!(f, "The special file {:?} can not be cloned.", path)
+            }
+            ErrorCode::SandboxExists(path) => {
+                write!(f, "The sandbox directory {:?} already exists.", path)
+            }
             ErrorCode::JsonSyntax(err) => write!(f, "JSON syntax error: {}", err),
             ErrorCode::YamlSyntax(err) => write!(f, "YAML syntax error: {}", err),
+            ErrorCode::GlobPattern(err) => write!(f, "Glob pattern error: {}", err),
+            ErrorCode::Cancelled => write!(
+                f,
+                "The build was cancelled after the failure of another entry."
+            ),
             ErrorCode::AcquireError(err) => write!(f, "Semaphore err: {}", err),
             ErrorCode::JoinError(err) => write!(f, "Join handle err: {}", err),
+            ErrorCode::PathEscapesSandbox(path) => {
+                write!(f, "The path {:?} escapes the sandbox directory.", path)
+            }
+            ErrorCode::PathNotDeclared(path) => {
+                write!(
+                    f,
+                    "The path {:?} is not declared in the configuration.",
+                    path
+                )
+            }
         }
     }
 }
@@ -242,16 +520,28 @@ impl Display for FsTesterError {
 }
 
 impl Display for ErrorImpl {
+    /// The entry and the location continue the sentence of the error message,
+    /// so the period of the message is moved after them.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if self.line == 0 {
-            Display::fmt(&self.code, f)
-        } else {
-            write!(
-                f,
-                "{} at line {} column {}",
-                self.code, self.line, self.column
-            )
+        let message = self.code.to_string();
+        let has_context = self.entry.is_some() || self.line != 0;
+        let (message, period) = match message.strip_suffix('.') {
+            Some(sentence) if has_context => (sentence, "."),
+            _ => (message.as_str(), ""),
+        };
+
+        f.write_str(message)?;
+        if let Some(entry) = &self.entry {
+            write!(f, " in the {} entry {:?}", entry.kind, entry.path)?;
+            if let Some(source) = &entry.source {
+                write!(f, " from {:?}", source)?;
+            }
+        }
+        if self.line != 0 {
+            write!(f, " at line {} column {}", self.line, self.column)?;
         }
+
+        f.write_str(period)
     }
 }
 
@@ -271,12 +561,23 @@ impl std::error::Error for FsTesterError {
             ErrorCode::Io(err) => Some(err),
             ErrorCode::JsonSyntax(err) => Some(err),
             ErrorCode::YamlSyntax(err) => Some(err),
+            ErrorCode::GlobPattern(err) => Some(err),
             ErrorCode::WalkDir(err) => Some(err),
             ErrorCode::AcquireError(err) => Some(err),
             ErrorCode::JoinError(err) => Some(err),
+            ErrorCode::BuildFailed(errors) => errors
+                .first()
+                .map(|error| error as &(dyn std::error::Error + 'static)),
             ErrorCode::EmptyConfig
             | ErrorCode::LinksNotAllowed
-            | ErrorCode::ShouldStartFromDirectory => None,
+            | ErrorCode::ShouldStartFromDirectory
+            | ErrorCode::InvalidConfig(_)
+            | ErrorCode::InvalidTemplate(_)
+            | ErrorCode::Cancelled
+            | ErrorCode::PathEscapesSandbox(_)
+            | ErrorCode::PathNotDeclared(_)
+            | ErrorCode::SpecialFile(_)
+            | ErrorCode::SandboxExists(_) => None,
         }
     }
 }
@@ -285,13 +586,18 @@ impl From<FsTesterError> for IoError {
     fn from(error: FsTesterError) -> Self {
         if let ErrorCode::Io(err) = error.err.code {
             err
+        } else if error.is_sandbox_exists() {
+            IoError::new(ErrorKind::AlreadyExists, error)
         } else {
             match error.classify() {
-                Category::Io => unreachable!(),
+                Category::Io => IoError::other(error),
                 Category::Syntax
                 | Category::ConfigFormat
                 | Category::NotAllowedSettings
                 | Category::Multitasking => IoError::new(ErrorKind::InvalidData, error),
+                Category::SandboxEscape | Category::NotDeclared => {
+                    IoError::new(ErrorKind::InvalidInput, error)
+                }
             }
         }
     }
@@ -324,6 +630,12 @@ impl From<serde_yaml::Error> for FsTesterError {
     }
 }
 
+impl From<ignore::Error> for FsTesterError {
+    fn from(err: ignore::Error) -> Self {
+        fs_tester_error!(ErrorCode::GlobPattern(err))
+    }
+}
+
 impl From<walkdir::Error> for FsTesterError {
     fn from(err: walkdir::Error) -> Self {
         fs_tester_error!(ErrorCode::WalkDir(err))
@@ -395,6 +707,86 @@ mod tests {
         assert_eq!(error.column(), 0);
     }
 
+    #[test]
+    fn test_invalid_template_error() {
+        let error = FsTesterError::invalid_template("the variable \"port\" is not defined");
+        assert!(error.is_config_format());
+        assert_eq!(
+            format!("{}", error),
+            "The template is invalid: the variable \"port\" is not defined."
+        );
+        assert_eq!(std::io::Error::from(error).kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_path_escapes_sandbox_error() {
+        let error = FsTesterError::path_escapes_sandbox(Path::new("../test.txt"));
+        assert!(error.is_sandbox_escape());
+        assert_eq!(
+            format!("{}", error),
+            "The path \"../test.txt\" escapes the sandbox directory."
+        );
+        assert_eq!(std::io::Error::from(error).kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_path_not_declared_error() {
+        let error = FsTesterError::path_not_declared(Path::new("sub/test.txt"));
+        assert!(error.is_not_declared());
+        assert_eq!(
+            format!("{}", error),
+            "The path \"sub/test.txt\" is not declared in the configuration."
+        );
+        assert_eq!(format!("{:?}", error.source()), "None");
+    }
+
+    #[test]
+    fn test_special_file_error() {
+        let error = FsTesterError::special_file(Path::new("source/fifo"));
+        assert!(error.is_io());
+        assert!(error.is_special_file());
+        assert!(error.io_error_kind().is_none());
+        assert_eq!(
+            format!("{}", error),
+            "The special file \"source/fifo\" can not be cloned."
+        );
+        assert_eq!(std::io::Error::from(error).kind(), ErrorKind::Other);
+    }
+
+    #[test]
+    fn test_sandbox_exists_error() {
+        let error = FsTesterError::sandbox_exists(Path::new("./test_dir"));
+        assert!(error.is_io());
+        assert!(error.is_sandbox_exists());
+        assert_eq!(
+            format!("{}", error),
+            "The sandbox directory \"./test_dir\" already exists."
+        );
+        assert_eq!(std::io::Error::from(error).kind(), ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn test_invalid_config_error() {
+        use crate::rfs::config::IssueKind;
+
+        let error = FsTesterError::invalid_config(vec![
+            ConfigIssue {
+                path: PathBuf::from("root"),
+                kind: IssueKind::EmptyName,
+            },
+            ConfigIssue {
+                path: PathBuf::from("root/a.txt"),
+                kind: IssueKind::DuplicateName,
+            },
+        ]);
+        assert!(error.is_config_format());
+        assert_eq!(error.config_issues().len(), 2);
+        assert_eq!(
+            format!("{}", error),
+            "The configuration is invalid:\n  root: the name is empty\n  root/a.txt: the name is used more than once"
+        );
+    }
+
     #[test]
     fn test_json_syntax_error() {
         let invalid_json = "{ invalid: json }";
@@ -440,6 +832,19 @@ mod tests {
         assert!(error.is_syntax());
     }
 
+    #[test]
+    fn test_glob_pattern_error() {
+        let glob_error = ignore::gitignore::GitignoreBuilder::new(".")
+            .add_line(None, "[z-a")
+            .expect_err("invalid range should be rejected");
+
+        let error = FsTesterError::from(glob_error);
+
+        assert!(error.is_syntax());
+        assert!(format!("{}", error).starts_with("Glob pattern error: "));
+        assert!(error.source().is_some());
+    }
+
     #[test]
     fn test_display_fmt_for_empty_config() {
         let error = FsTesterError::empty_config();
@@ -520,6 +925,109 @@ mod tests {
         assert_eq!(format!("{}", error), "YAML syntax error: mapping values are not allowed in this context at line 1 column 14 at line 1 column 14");
     }
 
+    #[test]
+    fn test_entry_context_of_nested_entry() {
+        use crate::rfs::config::file_content::FileContent;
+        use crate::rfs::config::{DirectoryConf, FileConf};
+
+        let file_entry = ConfigEntry::File(FileConf {
+            name: String::from("test.mp3"),
+            content: FileContent::OriginalFile(String::from("data/test.mp3")),
+            ..Default::default()
+        });
+        let dir_entry = |name: &str| {
+            ConfigEntry::Directory(DirectoryConf {
+                name: String::from(name),
+                ..Default::default()
+            })
+        };
+        let io_error = std::io::Error::new(std::io::ErrorKind::NotFound, "File not found");
+
+        let error = FsTesterError::io_error(io_error)
+            .within_entry(&file_entry)
+            .within_entry(&dir_entry("sub"))
+            .within_entry(&dir_entry("root"))
+            .locate_entry_in("- !directory\n    name: root\n    content:\n      - !directory\n          name: sub\n          content:\n            - !file\n                name: test.mp3\n");
+
+        assert_eq!(error.entry_path(), Some(Path::new("root/sub/test.mp3")));
+        assert_eq!(error.entry_kind(), Some("file"));
+        assert_eq!(error.entry_source(), Some(Path::new("data/test.mp3")));
+        assert_eq!((error.line(), error.column()), (8, 17));
+        assert_eq!(
+            format!("{}", error),
+            "IO error: File not found in the file entry \"root/sub/test.mp3\" from \"data/test.mp3\" at line 8 column 17"
+        );
+    }
+
+    #[test]
+    fn test_entry_context_should_follow_the_message_sentence() {
+        use crate::rfs::config::{DirectoryConf, FileConf};
+
+        let file_error = |name: &str| {
+            FsTesterError::invalid_template(String::from("the placeholder \"{{ b\" is not closed"))
+                .within_entry(&ConfigEntry::File(FileConf {
+                    name: String::from(name),
+                    ..Default::default()
+                }))
+        };
+        let root = ConfigEntry::Directory(DirectoryConf {
+            name: String::from("t"),
+            ..Default::default()
+        });
+
+        let error = file_error("x").within_entry(&root).locate_entry_in(
+            "- !directory\n    name: t\n    content:\n      - !file\n          name: x\n",
+        );
+        assert_eq!(
+            format!("{}", error),
+            "The template is invalid: the placeholder \"{{ b\" is not closed in the file entry \"t/x\" at line 5 column 11."
+        );
+
+        let error =
+            FsTesterError::build_failed(vec![file_error("x"), file_error("y")]).within_entry(&root);
+        assert_eq!(
+            format!("{}", error),
+            "2 entries failed to be built:\n  \
+            The template is invalid: the placeholder \"{{ b\" is not closed in the file entry \"t/x\".\n  \
+            The template is invalid: the placeholder \"{{ b\" is not closed in the file entry \"t/y\"."
+        );
+    }
+
+    #[test]
+    fn test_build_failed_error_should_prefix_all_entries() {
+        use crate::rfs::config::{DirectoryConf, FileConf};
+
+        let file_error = |name: &str| {
+            FsTesterError::special_file(Path::new(name)).within_entry(&ConfigEntry::File(
+                FileConf {
+                    name: String::from(name),
+                    ..Default::default()
+                },
+            ))
+        };
+        let root = ConfigEntry::Directory(DirectoryConf {
+            name: String::from("root"),
+            ..Default::default()
+        });
+
+        let error =
+            FsTesterError::build_failed(vec![file_error("a"), file_error("b")]).within_entry(&root);
+
+        let paths: Vec<_> = error
+            .build_errors()
+            .iter()
+            .map(|error| error.entry_path())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![Some(Path::new("root/a")), Some(Path::new("root/b"))]
+        );
+        assert!(error.entry_path().is_none());
+        assert!(error.is_io());
+        assert!(error.source().is_some());
+        assert_eq!(error.into_build_errors().len(), 2);
+    }
+
     #[test]
     fn test_display_fmt_with_sandbox_dir() {
         let mut error = FsTesterError::empty_config();
@@ -0,0 +1,71 @@
+//! FsTesterOptions tunes the sandbox behaviour which is not a part of the configuration.
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+
+use super::sandbox_naming::SandboxNaming;
+
+const KEEP_ON_FAILURE_VAR_NAME: &str = "KEEP_ON_FAILURE";
+const SANDBOX_ROOT_VAR_NAME: &str = "RFS_TESTER_ROOT";
+
+/// Returns true if the environment variable is set to any value except "N".
+pub(crate) fn env_flag(var_name: &str) -> bool {
+    env::var(var_name).unwrap_or_else(|_| "N".to_string()) != "N"
+}
+
+/// Returns the directory for sandboxes created without the start point:
+/// the RFS_TESTER_ROOT environment variable if it is set, otherwise the system temp directory.
+pub(crate) fn default_base_dir() -> PathBuf {
+    env::var_os(SANDBOX_ROOT_VAR_NAME)
+        .filter(|root| !root.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(env::temp_dir)
+}
+
+/// The options of the FsTester instance.
+///
+/// ```rust
+/// # use rfs_tester::{FsTester, FsTesterOptions};
+/// let options = FsTesterOptions {
+///     keep_on_failure: true,
+///     write_manifest: true,
+///     ..Default::default()
+/// };
+/// # let _ = options;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FsTesterOptions {
+    /// Keeps the sandbox directory if the test closure returned an error or panicked,
+    /// so its state can be inspected after the test. The path of the retained sandbox
+    /// is printed to the stderr.
+    ///
+    /// It can be also enabled for all testers by setting the KEEP_ON_FAILURE
+    /// environment variable to "Y".
+    pub keep_on_failure: bool,
+
+    /// Writes the `<sandbox>.manifest.yaml` file next to the retained sandbox.
+    /// The manifest describes the failure and the configuration which produced the sandbox.
+    pub write_manifest: bool,
+
+    /// The naming strategy of the sandbox directory, random suffixes by default.
+    pub naming: SandboxNaming,
+
+    /// Builds all entries and reports every failed one with [`FsTesterError::build_errors`],
+    /// so a broken configuration can be fixed in one iteration. By default the build
+    /// is cancelled on the first failure and only that failure is reported.
+    ///
+    /// [`FsTesterError::build_errors`]: crate::FsTesterError::build_errors
+    pub collect_errors: bool,
+
+    /// The variables of the `inline_template` file content, e.g. `{{port}}`.
+    /// The built-in `sandbox_root` and `entry_path` variables can not be replaced.
+    /// The literal `{{` is written in the template as `{{{{`.
+    pub variables: HashMap<String, String>,
+}
+
+impl FsTesterOptions {
+    /// Returns true if the sandbox should be kept on failure by the options or the environment.
+    pub(crate) fn keep_on_failure(&self) -> bool {
+        self.keep_on_failure || env_flag(KEEP_ON_FAILURE_VAR_NAME)
+    }
+}
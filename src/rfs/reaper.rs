@@ -0,0 +1,274 @@
+//! The `reaper` module marks the sandbox directories with their owner process,
+//! so the sandboxes left by crashed or killed test runs can be found and removed.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use serde::{Deserialize, Serialize};
+
+use super::config::Timestamp;
+use super::fs_tester::FsTester;
+use super::fs_tester_error::Result;
+
+/// The suffix of the marker file placed next to the sandbox directory.
+const MARKER_SUFFIX: &str = ".rfs_tester.yaml";
+
+/// The content of the marker file, which identifies the directory as a sandbox.
+#[derive(Debug, Serialize, Deserialize)]
+struct SandboxMarker {
+    /// The id of the process which created the sandbox.
+    pid: u32,
+
+    /// The creation time of the sandbox.
+    created: Timestamp,
+
+    /// The identity of the sandbox directory in the file system, so a directory
+    /// created later with the same name is not taken for the sandbox.
+    #[serde(default)]
+    identity: Option<String>,
+}
+
+/// Returns the identity of the directory: the device and the inode on unix,
+/// and the creation time where the file system records it.
+fn dir_identity(dir: &Path) -> std::io::Result<String> {
+    let metadata = fs::symlink_metadata(dir)?;
+    let mut identity = vec![];
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        identity.push(metadata.dev().to_string());
+        identity.push(metadata.ino().to_string());
+    }
+
+    if let Ok(created) = metadata.created() {
+        let created = created
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        identity.push(created.as_nanos().to_string());
+    }
+
+    Ok(identity.join(":"))
+}
+
+/// Returns the path of the marker file of the sandbox directory.
+fn marker_path(sandbox_dir: &Path) -> PathBuf {
+    let mut marker_path = sandbox_dir.as_os_str().to_owned();
+    marker_path.push(MARKER_SUFFIX);
+    PathBuf::from(marker_path)
+}
+
+/// Writes the marker file of the sandbox created by the current process.
+pub(crate) fn write_marker(sandbox_dir: &Path) -> Result<()> {
+    let marker = SandboxMarker {
+        pid: std::process::id(),
+        created: Timestamp::Absolute(SystemTime::now()),
+        identity: Some(dir_identity(sandbox_dir)?),
+    };
+    fs::write(marker_path(sandbox_dir), serde_yaml::to_string(&marker)?)?;
+
+    Ok(())
+}
+
+/// Removes the marker file of the sandbox, the missing marker is not an error.
+pub(crate) fn remove_marker(sandbox_dir: &Path) -> std::io::Result<()> {
+    match fs::remove_file(marker_path(sandbox_dir)) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+        _ => Ok(()),
+    }
+}
+
+/// Returns true if the process is running. If it can not be determined, the process
+/// is considered running, so its sandbox is removed only by the age.
+fn is_process_alive(pid: u32) -> bool {
+    if pid == std::process::id() {
+        return true;
+    }
+
+    #[cfg(target_os = "linux")]
+    return Path::new("/proc").join(pid.to_string()).exists();
+
+    #[cfg(all(unix, not(target_os = "linux")))]
+    return std::process::Command::new("kill")
+        .arg("-0")
+        .arg(pid.to_string())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_or(true, |status| status.success());
+
+    #[cfg(not(unix))]
+    return true;
+}
+
+impl FsTester {
+    /// Removes the stale sandboxes in the base directory and returns their paths.
+    ///
+    /// Every sandbox has the `<sandbox>.rfs_tester.yaml` marker file next to it
+    /// with the id of the owner process and the creation time. The sandbox is stale
+    /// if its owner process is gone, or it is older than `max_age` if it is set.
+    /// The directories without the marker are never touched, including the sandboxes
+    /// kept on failure, whose marker is removed when they are retained. The marker
+    /// also records the identity of the sandbox directory, and a directory which
+    /// does not match it is kept, only its stale marker is removed.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use rfs_tester::FsTester;
+    /// # use std::time::Duration;
+    /// let removed = FsTester::reap_stale_sandboxes(
+    ///     std::env::temp_dir(),
+    ///     Some(Duration::from_secs(24 * 3600)),
+    /// )
+    /// .unwrap();
+    /// # let _ = removed;
+    /// ```
+    pub fn reap_stale_sandboxes(
+        base_dir: impl AsRef<Path>,
+        max_age: Option<Duration>,
+    ) -> Result<Vec<PathBuf>> {
+        let now = SystemTime::now();
+        let mut removed = vec![];
+
+        for entry in fs::read_dir(base_dir.as_ref())? {
+            let marker_path = entry?.path();
+            let Some(sandbox_dir) = marker_path
+                .to_str()
+                .and_then(|path| path.strip_suffix(MARKER_SUFFIX))
+                .map(PathBuf::from)
+            else {
+                continue;
+            };
+
+            // The marker is written by another tool or version, so its directory is kept.
+            let marker = fs::read_to_string(&marker_path)
+                .ok()
+                .and_then(|marker| serde_yaml::from_str::<SandboxMarker>(&marker).ok());
+            let Some(marker) = marker else {
+                continue;
+            };
+
+            let age = now
+                .duration_since(marker.created.resolve(now))
+                .unwrap_or_default();
+            let is_stale = !is_process_alive(marker.pid) || max_age.is_some_and(|max| age > max);
+            if !is_stale {
+                continue;
+            }
+
+            // The directory may be recreated by someone else after the sandbox is gone.
+            let is_sandbox = marker.identity.is_some_and(|identity| {
+                fs::symlink_metadata(&sandbox_dir).is_ok_and(|metadata| metadata.is_dir())
+                    && dir_identity(&sandbox_dir).is_ok_and(|current| current == identity)
+            });
+            if is_sandbox {
+                Self::remove_sandbox(&sandbox_dir)?;
+                removed.push(sandbox_dir.clone());
+            }
+            remove_marker(&sandbox_dir)?;
+        }
+
+        removed.sort();
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const YAML_EMPTY_DIR: &str = "
+    - !directory
+        name: test_reaper
+        content: []
+    ";
+
+    fn write_foreign_marker(sandbox_dir: &Path, pid: u32, created: SystemTime) -> Result<()> {
+        fs::create_dir(sandbox_dir)?;
+        let marker = SandboxMarker {
+            pid,
+            created: Timestamp::Absolute(created),
+            identity: Some(dir_identity(sandbox_dir)?),
+        };
+        fs::write(marker_path(sandbox_dir), serde_yaml::to_string(&marker)?)?;
+        Ok(())
+    }
+
+    fn finished_process_id() -> Result<u32> {
+        let mut child = std::process::Command::new(std::env::current_exe()?)
+            .arg("--list")
+            .stdout(std::process::Stdio::null())
+            .spawn()?;
+        let pid = child.id();
+        child.wait()?;
+        Ok(pid)
+    }
+
+    #[test]
+    fn sandbox_marker_should_follow_the_sandbox_lifetime() -> Result<()> {
+        let tester = FsTester::new(YAML_EMPTY_DIR, ".")?;
+        let marker_path = marker_path(tester.root());
+
+        let marker: SandboxMarker = serde_yaml::from_str(&fs::read_to_string(&marker_path)?)?;
+        drop(tester);
+
+        assert_eq!(marker.pid, std::process::id());
+        assert!(!marker_path.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn reaper_should_remove_only_marked_stale_sandboxes() -> Result<()> {
+        let base = FsTester::new(YAML_EMPTY_DIR, ".")?;
+        let live = FsTester::new(YAML_EMPTY_DIR, &base.base_dir)?;
+        let dead_owner_dir = base.root().join("dead_owner");
+        write_foreign_marker(&dead_owner_dir, finished_process_id()?, SystemTime::now())?;
+        let unmarked_dir = base.root().join("unmarked");
+        fs::create_dir(&unmarked_dir)?;
+
+        let removed = FsTester::reap_stale_sandboxes(base.root(), None)?;
+
+        assert_eq!(removed, vec![dead_owner_dir.clone()]);
+        assert!(!marker_path(&dead_owner_dir).exists());
+        assert!(live.root().is_dir());
+        assert!(unmarked_dir.is_dir());
+        Ok(())
+    }
+
+    #[test]
+    fn reaper_should_keep_foreign_directory_with_stale_marker() -> Result<()> {
+        let base = FsTester::new(YAML_EMPTY_DIR, ".")?;
+        let foreign_dir = base.root().join("foreign");
+        fs::create_dir(&foreign_dir)?;
+        // The marker of a sandbox which was removed without its marker.
+        let marker = SandboxMarker {
+            pid: finished_process_id()?,
+            created: Timestamp::Absolute(SystemTime::now()),
+            identity: Some(dir_identity(base.root())?),
+        };
+        fs::write(marker_path(&foreign_dir), serde_yaml::to_string(&marker)?)?;
+
+        let removed = FsTester::reap_stale_sandboxes(base.root(), None)?;
+
+        assert!(removed.is_empty());
+        assert!(foreign_dir.is_dir());
+        assert!(!marker_path(&foreign_dir).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn reaper_should_remove_sandboxes_older_than_max_age() -> Result<()> {
+        let base = FsTester::new(YAML_EMPTY_DIR, ".")?;
+        let old_dir = base.root().join("old");
+        let day = Duration::from_secs(24 * 3600);
+        write_foreign_marker(&old_dir, std::process::id(), SystemTime::now() - 2 * day)?;
+        let new_dir = base.root().join("new");
+        write_foreign_marker(&new_dir, std::process::id(), SystemTime::now())?;
+
+        let removed = FsTester::reap_stale_sandboxes(base.root(), Some(day))?;
+
+        assert_eq!(removed, vec![old_dir]);
+        assert!(new_dir.is_dir());
+        Ok(())
+    }
+}
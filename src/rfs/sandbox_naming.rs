@@ -0,0 +1,200 @@
+//! The `sandbox_naming` module chooses the name of the sandbox directory,
+//! so the sandbox paths in logs and snapshot outputs can be made stable.
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use rand::{Rng, RngCore};
+
+use super::content_generator::seeded_rng;
+use super::fs_tester_error::{FsTesterError, Result};
+
+/// The counter shared by all testers of the process using the `Counter` strategy.
+static SANDBOX_COUNTER: AtomicU64 = AtomicU64::new(1);
+
+/// The strategy of the sandbox directory naming. The sandbox directory is the root
+/// entry of the configuration, its name gets a suffix to avoid collisions.
+///
+/// ```rust
+/// # use rfs_tester::{FsTester, FsTesterOptions, SandboxNaming};
+/// let tester = FsTester::with_options(
+///     "
+///     - !directory
+///         name: test_doc_sandbox_naming
+///         content: []
+///     ",
+///     ".",
+///     FsTesterOptions {
+///         naming: SandboxNaming::Exact(String::from("test_doc_sandbox_naming_exact")),
+///         ..Default::default()
+///     },
+/// )
+/// .unwrap();
+/// assert!(tester.root().ends_with("test_doc_sandbox_naming_exact"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SandboxNaming {
+    /// `<name>_<random u64>`, the default.
+    #[default]
+    Random,
+
+    /// `<name>_<u64>` where the numbers are taken from the ChaCha8 random generator
+    /// with the given seed, so the same seed gives the same name in every run,
+    /// on every platform and with every version.
+    /// If the directory exists, the next number of the sequence is tried.
+    Seeded(u64),
+
+    /// `<name>_<n>` where `n` is the counter shared by all testers of the process.
+    /// The names are stable across runs while the testers are created in the same order.
+    /// If the directory exists, the next number is tried.
+    Counter,
+
+    /// `<name>_<test>` where `<test>` is the name of the current thread, which the test
+    /// harness sets to the test path, e.g. `tests_sandbox_naming` for `tests::sandbox_naming`.
+    /// The characters other than ASCII letters, digits, `-` and `_` are replaced with `_`.
+    /// Outside of a test thread it falls back to the `Counter` strategy.
+    /// If the directory exists, [`FsTesterError::is_sandbox_exists`] error is returned.
+    TestName,
+
+    /// The exact name supplied by the caller, the name of the root entry is ignored.
+    /// If the directory exists, [`FsTesterError::is_sandbox_exists`] error is returned.
+    /// The name should be a single path component, otherwise
+    /// [`FsTesterError::is_sandbox_escape`] error is returned.
+    Exact(String),
+}
+
+impl SandboxNaming {
+    /// Creates the sandbox directory in the base directory and returns its path.
+    /// The directory is created atomically, so two testers never share the same sandbox.
+    pub(crate) fn create_sandbox(&self, base_dir: &Path, name: &str) -> Result<PathBuf> {
+        if let SandboxNaming::Exact(exact_name) = self {
+            let mut components = Path::new(exact_name).components();
+            if !matches!(
+                (components.next(), components.next()),
+                (Some(Component::Normal(_)), None)
+            ) {
+                return Err(FsTesterError::path_escapes_sandbox(Path::new(exact_name)));
+            }
+        }
+
+        let mut seeded_rng = match self {
+            SandboxNaming::Seeded(seed) => Some(seeded_rng(*seed)),
+            _ => None,
+        };
+
+        loop {
+            let (dir_name, retry) = match self {
+                SandboxNaming::Random => {
+                    (format!("{}_{}", name, rand::rng().random::<u64>()), true)
+                }
+                SandboxNaming::Seeded(_) => {
+                    let rng = seeded_rng
+                        .as_mut()
+                        .expect("the seeded generator should be set");
+                    (format!("{}_{}", name, rng.next_u64()), true)
+                }
+                SandboxNaming::Counter => (Self::counter_name(name), true),
+                SandboxNaming::TestName => match Self::test_name() {
+                    Some(test_name) => (format!("{}_{}", name, test_name), false),
+                    None => (Self::counter_name(name), true),
+                },
+                SandboxNaming::Exact(exact_name) => (exact_name.clone(), false),
+            };
+
+            let path = base_dir.join(dir_name);
+            match std::fs::create_dir(&path) {
+                Ok(()) => return Ok(path),
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                    if !retry {
+                        return Err(FsTesterError::sandbox_exists(&path));
+                    }
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    fn counter_name(name: &str) -> String {
+        format!(
+            "{}_{}",
+            name,
+            SANDBOX_COUNTER.fetch_add(1, Ordering::Relaxed)
+        )
+    }
+
+    /// The sanitized name of the current test thread.
+    fn test_name() -> Option<String> {
+        let thread = std::thread::current();
+        let thread_name = thread.name().filter(|thread_name| *thread_name != "main")?;
+
+        Some(
+            thread_name
+                .replace("::", "_")
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_name_should_be_taken_from_test_thread() {
+        assert_eq!(
+            SandboxNaming::test_name().as_deref(),
+            Some("rfs_sandbox_naming_tests_test_name_should_be_taken_from_test_thread")
+        );
+    }
+
+    #[test]
+    fn seeded_naming_should_repeat_names_for_the_same_seed() -> Result<()> {
+        let base_dir = SandboxNaming::Random.create_sandbox(Path::new("."), "test_seeded_base")?;
+
+        let first = SandboxNaming::Seeded(42).create_sandbox(&base_dir, "sandbox")?;
+        let second = SandboxNaming::Seeded(42).create_sandbox(&base_dir, "sandbox")?;
+        std::fs::remove_dir(&first)?;
+        let repeated = SandboxNaming::Seeded(42).create_sandbox(&base_dir, "sandbox")?;
+
+        std::fs::remove_dir_all(&base_dir)?;
+        assert_ne!(first, second);
+        assert_eq!(first, repeated);
+        assert_eq!(first, base_dir.join("sandbox_6424161053832095879"));
+        Ok(())
+    }
+
+    #[test]
+    fn exact_naming_should_detect_collision() -> Result<()> {
+        let naming = SandboxNaming::Exact(String::from("test_exact_naming_collision"));
+
+        let path = naming.create_sandbox(Path::new("."), "ignored")?;
+        let result = naming.create_sandbox(Path::new("."), "ignored");
+
+        std::fs::remove_dir(&path)?;
+        assert_eq!(path, Path::new(".").join("test_exact_naming_collision"));
+        assert!(result.is_err_and(|error| error.is_sandbox_exists()));
+        Ok(())
+    }
+
+    #[test]
+    fn exact_naming_should_reject_paths() {
+        for exact_name in ["", ".", "..", "../escaped", "nested/name", "/absolute"] {
+            let naming = SandboxNaming::Exact(String::from(exact_name));
+
+            let result = naming.create_sandbox(Path::new("."), "ignored");
+
+            assert!(
+                result.is_err_and(|error| error.is_sandbox_escape()),
+                "{exact_name:?} should be rejected"
+            );
+        }
+    }
+}
@@ -0,0 +1,71 @@
+//! The `sparse` module copies files with holes, so the copy takes as little disk space as its source.
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Returns the `(start, end)` ranges of the file data, the gaps between them are holes.
+/// The holes are found with `SEEK_DATA` and `SEEK_HOLE`, if the file system does not support
+/// them, the whole file is a single data range.
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "freebsd"))]
+fn data_ranges(file: &File, len: u64) -> io::Result<Vec<(u64, u64)>> {
+    use std::os::fd::AsRawFd;
+
+    let seek = |offset: u64, whence: libc::c_int| -> io::Result<Option<u64>> {
+        let offset = libc::off_t::try_from(offset)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        // SAFETY: lseek only moves the position of the open file descriptor.
+        let position = unsafe { libc::lseek(file.as_raw_fd(), offset, whence) };
+        if position >= 0 {
+            return Ok(Some(position as u64));
+        }
+
+        let err = io::Error::last_os_error();
+        // ENXIO means there is no data after the offset.
+        if err.raw_os_error() == Some(libc::ENXIO) {
+            Ok(None)
+        } else {
+            Err(err)
+        }
+    };
+
+    let mut ranges = vec![];
+    let mut offset = 0;
+    while offset < len {
+        let start = match seek(offset, libc::SEEK_DATA) {
+            Ok(Some(start)) => start,
+            Ok(None) => break,
+            // EINVAL or EOPNOTSUPP means the file system does not support SEEK_DATA.
+            Err(err)
+                if ranges.is_empty()
+                    && matches!(
+                        err.raw_os_error(),
+                        Some(libc::EINVAL) | Some(libc::EOPNOTSUPP)
+                    ) =>
+            {
+                return Ok(vec![(0, len)]);
+            }
+            Err(err) => return Err(err),
+        };
+        let end = seek(start, libc::SEEK_HOLE)?.unwrap_or(len).min(len);
+        ranges.push((start, end));
+        offset = end;
+    }
+
+    Ok(ranges)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "freebsd")))]
+fn data_ranges(_file: &File, len: u64) -> io::Result<Vec<(u64, u64)>> {
+    Ok(vec![(0, len)])
+}
+
+/// Copies the data ranges of the source file and leaves the holes between them in the destination.
+pub(crate) fn copy_sparse(mut src: &File, mut dst: &File) -> io::Result<()> {
+    let len = src.metadata()?.len();
+    for (start, end) in data_ranges(src, len)? {
+        src.seek(SeekFrom::Start(start))?;
+        dst.seek(SeekFrom::Start(start))?;
+        io::copy(&mut src.take(end - start), &mut dst)?;
+    }
+
+    dst.set_len(len)
+}
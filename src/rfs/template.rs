@@ -0,0 +1,213 @@
+//! The `template` module renders the `inline_template` file content. The placeholders
+//! refer to the sandbox path, so the templates are rendered after the sandbox directory
+//! is created and before its content is built.
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+use super::config::clone_directory_conf::CloneDirectoryConf;
+use super::config::directory_conf::DirectoryConf;
+use super::config::file_content::{FileContent, SparseExtent};
+use super::config::{ArchiveConf, ConfigEntry, FileConf};
+use super::fs_tester_error::{FsTesterError, Result};
+
+const PLACEHOLDER_START: &str = "{{";
+/// The doubled placeholder start is the escape of the literal `{{`.
+const ESCAPED_PLACEHOLDER_START: &str = "{{{{";
+const PLACEHOLDER_END: &str = "}}";
+const SANDBOX_ROOT_VARIABLE: &str = "sandbox_root";
+const ENTRY_PATH_VARIABLE: &str = "entry_path";
+const ENV_VARIABLE_PREFIX: &str = "env.";
+
+/// The values of the template placeholders shared by all entries of the sandbox.
+pub(crate) struct TemplateContext<'a> {
+    /// The absolute path of the sandbox directory.
+    sandbox_root: PathBuf,
+    variables: &'a HashMap<String, String>,
+}
+
+impl<'a> TemplateContext<'a> {
+    pub(crate) fn new(sandbox_dir: &Path, variables: &'a HashMap<String, String>) -> Result<Self> {
+        Ok(TemplateContext {
+            sandbox_root: std::fs::canonicalize(sandbox_dir)?,
+            variables,
+        })
+    }
+
+    /// Returns the copy of the root entry, which is the sandbox directory itself,
+    /// with the rendered templates.
+    pub(crate) fn render_root(&self, entry: &ConfigEntry) -> Result<ConfigEntry> {
+        self.render_entry(entry, &self.sandbox_root)
+    }
+
+    /// Returns the copy of the entry placed at the absolute path with the rendered templates.
+    fn render_entry(&self, entry: &ConfigEntry, path: &Path) -> Result<ConfigEntry> {
+        Ok(match entry {
+            ConfigEntry::Directory(conf) => ConfigEntry::Directory(DirectoryConf {
+                content: self.render_children(&conf.content, path)?,
+                ..conf.clone()
+            }),
+            ConfigEntry::CloneDirectory(conf) => ConfigEntry::CloneDirectory(CloneDirectoryConf {
+                content: self.render_children(&conf.content, path)?,
+                ..conf.clone()
+            }),
+            ConfigEntry::File(conf) => ConfigEntry::File(FileConf {
+                content: self.render_content(&conf.content, path)?,
+                ..conf.clone()
+            }),
+            ConfigEntry::Archive(conf) => ConfigEntry::Archive(ArchiveConf {
+                source: self.render_content(&conf.source, path)?,
+                ..conf.clone()
+            }),
+            ConfigEntry::Link(_) | ConfigEntry::Symlink(_) => entry.clone(),
+        })
+    }
+
+    fn render_children(
+        &self,
+        content: &[ConfigEntry],
+        dir_path: &Path,
+    ) -> Result<Vec<ConfigEntry>> {
+        content
+            .iter()
+            .map(|child| {
+                self.render_entry(child, &dir_path.join(child.name()))
+                    .map_err(|err| err.within_entry(child))
+            })
+            .collect()
+    }
+
+    fn render_content(&self, content: &FileContent, entry_path: &Path) -> Result<FileContent> {
+        Ok(match content {
+            FileContent::InlineTemplate(template) => {
+                FileContent::InlineText(self.render(template, entry_path)?)
+            }
+            FileContent::Sparse { size, extents } => FileContent::Sparse {
+                size: *size,
+                extents: extents
+                    .iter()
+                    .map(|extent| {
+                        Ok(SparseExtent {
+                            offset: extent.offset,
+                            content: self.render_content(&extent.content, entry_path)?,
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+            },
+            FileContent::Gzip { content } => FileContent::Gzip {
+                content: Box::new(self.render_content(content, entry_path)?),
+            },
+            content => content.clone(),
+        })
+    }
+
+    /// Replaces the placeholders like `{{ name }}` with the values of the variables
+    /// and the escaped `{{{{` with the literal `{{`.
+    fn render(&self, template: &str, entry_path: &Path) -> Result<String> {
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find(PLACEHOLDER_START) {
+            rendered.push_str(&rest[..start]);
+            if rest[start..].starts_with(ESCAPED_PLACEHOLDER_START) {
+                rendered.push_str(PLACEHOLDER_START);
+                rest = &rest[start + ESCAPED_PLACEHOLDER_START.len()..];
+                continue;
+            }
+            let placeholder = &rest[start + PLACEHOLDER_START.len()..];
+            let end = placeholder.find(PLACEHOLDER_END).ok_or_else(|| {
+                FsTesterError::invalid_template(format!(
+                    "the placeholder {:?} is not closed",
+                    &rest[start..]
+                ))
+            })?;
+            rendered.push_str(&self.value(placeholder[..end].trim(), entry_path)?);
+            rest = &placeholder[end + PLACEHOLDER_END.len()..];
+        }
+        rendered.push_str(rest);
+
+        Ok(rendered)
+    }
+
+    fn value<'v>(&'v self, name: &str, entry_path: &'v Path) -> Result<Cow<'v, str>> {
+        match name {
+            SANDBOX_ROOT_VARIABLE => Ok(self.sandbox_root.to_string_lossy()),
+            ENTRY_PATH_VARIABLE => Ok(entry_path.to_string_lossy()),
+            _ => match name.strip_prefix(ENV_VARIABLE_PREFIX) {
+                Some(var_name) => env::var(var_name).map(Cow::Owned).map_err(|_| {
+                    FsTesterError::invalid_template(format!(
+                        "the environment variable {:?} is not set",
+                        var_name
+                    ))
+                }),
+                None => self
+                    .variables
+                    .get(name)
+                    .map(|value| Cow::Borrowed(value.as_str()))
+                    .ok_or_else(|| {
+                        FsTesterError::invalid_template(format!(
+                            "the variable {:?} is not defined",
+                            name
+                        ))
+                    }),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_should_be_replaced() -> Result<()> {
+        let variables = HashMap::from([(String::from("port"), String::from("8080"))]);
+        let context = TemplateContext::new(Path::new("."), &variables)?;
+        let entry_path = context.sandbox_root.join("config.toml");
+
+        let rendered = context.render(
+            "root = {{sandbox_root}}, self = {{ entry_path }}, port = {{port}}",
+            &entry_path,
+        )?;
+
+        assert_eq!(
+            rendered,
+            format!(
+                "root = {}, self = {}, port = 8080",
+                context.sandbox_root.display(),
+                entry_path.display()
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn escaped_placeholder_start_should_be_rendered_literally() -> Result<()> {
+        let variables = HashMap::from([(String::from("name"), String::from("value"))]);
+        let context = TemplateContext::new(Path::new("."), &variables)?;
+
+        let rendered = context.render(
+            "{{{{name}} is {{name}}, {{{{{{name}}}}, }} and {",
+            Path::new("test.txt"),
+        )?;
+
+        assert_eq!(rendered, "{{name}} is value, {{value}}, }} and {");
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_and_unclosed_placeholders_should_be_rejected() -> Result<()> {
+        let variables = HashMap::new();
+        let context = TemplateContext::new(Path::new("."), &variables)?;
+
+        for template in [
+            "{{missing}}",
+            "{{env.RFS_TESTER_MISSING_VARIABLE}}",
+            "{{sandbox_root",
+        ] {
+            let error = context.render(template, Path::new("test.txt")).unwrap_err();
+            assert!(error.is_config_format(), "{}", error);
+        }
+        Ok(())
+    }
+}
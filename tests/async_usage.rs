@@ -0,0 +1,27 @@
+use rfs_tester::FsTester;
+
+#[tokio::test]
+async fn async_test_file_creation() {
+    let config_str = r#"---
+    - !directory
+        name: test
+        content:
+          - !file
+              name: test.txt
+              content:
+                !inline_text "Hello, world!"
+    "#;
+
+    // Creates a temporary file system on the current runtime
+    let tester = FsTester::new_async(config_str, ".").await.unwrap();
+
+    // Performs the test
+    tester
+        .perform_fs_test_async(async |dirname| {
+            let file_path = std::path::PathBuf::from(dirname).join("test.txt");
+            let content = tokio::fs::read_to_string(file_path).await?;
+            assert_eq!(content, "Hello, world!");
+            Ok(())
+        })
+        .await;
+}
@@ -0,0 +1,33 @@
+use std::path::Path;
+
+use rfs_tester::FsTester;
+
+#[test]
+fn sandbox_should_be_created_in_rfs_tester_root() {
+    let config_str = r#"---
+    - !directory
+        name: test
+        content:
+          - !file
+              name: test.txt
+              content:
+                !inline_text "Hello, world!"
+    "#;
+
+    // This is the only test of the binary, so the variable does not affect other tests
+    std::env::set_var("RFS_TESTER_ROOT", "target/rfs_tester_root");
+
+    // The sandbox is created in RFS_TESTER_ROOT when the start point is empty
+    let tester = FsTester::new(config_str, "").unwrap();
+    assert_eq!(
+        tester.root().parent(),
+        Some(Path::new("target/rfs_tester_root"))
+    );
+
+    tester.perform_fs_test(|dirname| {
+        let file_path = std::path::PathBuf::from(dirname).join("test.txt");
+        let content = std::fs::read_to_string(file_path)?;
+        assert_eq!(content, "Hello, world!");
+        Ok(())
+    });
+}
@@ -0,0 +1,26 @@
+use rfs_tester::FsTester;
+
+#[test]
+fn template_should_read_environment_variables() {
+    let config_str = r#"---
+    - !directory
+        name: test_template_env
+        content:
+          - !file
+              name: config.toml
+              content: !inline_template |
+                package = "{{env.RFS_TESTER_TEMPLATE_PACKAGE}}"
+    "#;
+
+    // This is the only test of the binary, so the variable does not affect other tests
+    std::env::set_var("RFS_TESTER_TEMPLATE_PACKAGE", "rfs_tester");
+
+    let tester = FsTester::new(config_str, ".").unwrap();
+
+    tester.perform_fs_test(|dirname| {
+        let file_path = std::path::PathBuf::from(dirname).join("config.toml");
+        let content = std::fs::read_to_string(file_path)?;
+        assert_eq!(content, "package = \"rfs_tester\"\n");
+        Ok(())
+    });
+}